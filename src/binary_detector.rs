@@ -0,0 +1,165 @@
+use std::path::Path;
+use sha2::{Digest, Sha256};
+
+/// Number of leading bytes inspected when looking for NUL bytes.
+const SNIFF_LENGTH: usize = 8000;
+
+/// Magic numbers that identify binary formats, with their MIME types.
+const MAGIC_NUMBERS: &[(&[u8], &str)] = &[
+    (b"\x89PNG\r\n\x1a\n", "image/png"),
+    (b"\xFF\xD8\xFF", "image/jpeg"),
+    (b"GIF87a", "image/gif"),
+    (b"GIF89a", "image/gif"),
+    (b"II*\x00", "image/tiff"),
+    (b"MM\x00*", "image/tiff"),
+    (b"%PDF-", "application/pdf"),
+    (b"PK\x03\x04", "application/zip"),
+    (b"PK\x05\x06", "application/zip"),
+    (b"\x1F\x8B", "application/gzip"),
+    (b"BZh91AY&SY", "application/x-bzip2"),
+    (b"\xFD7zXZ\x00", "application/x-xz"),
+    (b"7z\xBC\xAF\x27\x1C", "application/x-7z-compressed"),
+    (b"Rar!\x1A\x07", "application/vnd.rar"),
+    (b"\x7FELF", "application/x-elf"),
+    (b"\xCA\xFE\xBA\xBE", "application/java-vm"),
+    (b"\x00asm", "application/wasm"),
+    (b"SQLite format 3\x00", "application/vnd.sqlite3"),
+    (b"OggS", "audio/ogg"),
+    (b"fLaC", "audio/flac"),
+    (b"wOFF", "font/woff"),
+    (b"wOF2", "font/woff2"),
+];
+
+/// MIME types guessed from the file extension when no magic number matches.
+const EXTENSION_MIME_TYPES: &[(&str, &str)] = &[
+    ("bmp", "image/bmp"),
+    ("ico", "image/vnd.microsoft.icon"),
+    ("webp", "image/webp"),
+    ("jar", "application/java-archive"),
+    ("war", "application/java-archive"),
+    ("exe", "application/vnd.microsoft.portable-executable"),
+    ("dll", "application/vnd.microsoft.portable-executable"),
+    ("so", "application/x-sharedlib"),
+    ("ttf", "font/ttf"),
+    ("otf", "font/otf"),
+    ("mp3", "audio/mpeg"),
+    ("wav", "audio/wav"),
+    ("mp4", "video/mp4"),
+];
+
+/// Metadata describing a binary file, used in place of its content.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BinaryInfo {
+    /// Size of the file in bytes.
+    pub size: u64,
+    /// Best-effort MIME type guess.
+    pub mime_type: &'static str,
+    /// Hex-encoded SHA-256 hash of the file content.
+    pub sha256: String,
+}
+
+/// Determines whether the given bytes look like binary data.
+///
+/// A file is considered binary when it starts with a known magic number or when a NUL
+/// byte occurs within its first bytes.
+///
+/// # Arguments
+///
+/// * `bytes` - The raw file content.
+///
+/// # Returns
+///
+/// * `bool` - `true` if the content is binary, `false` otherwise.
+pub fn is_binary(bytes: &[u8]) -> bool {
+    if has_magic_number(bytes) {
+        return true;
+    }
+    let sniffed = &bytes[..bytes.len().min(SNIFF_LENGTH)];
+    sniffed.contains(&0)
+}
+
+/// Determines whether the given bytes start with the magic number of a binary format.
+///
+/// # Arguments
+///
+/// * `bytes` - The raw file content.
+///
+/// # Returns
+///
+/// * `bool` - `true` if a known magic number matches, `false` otherwise.
+pub fn has_magic_number(bytes: &[u8]) -> bool {
+    magic_mime_type(bytes).is_some()
+}
+
+/// Determines whether decoded text looks like binary data.
+///
+/// Used for content decoded from UTF-16, whose raw bytes contain NUL bytes even for
+/// plain text: binary data that merely starts with a byte order mark decodes to NUL
+/// characters.
+///
+/// # Arguments
+///
+/// * `text` - The decoded file content.
+///
+/// # Returns
+///
+/// * `bool` - `true` if the content is binary, `false` otherwise.
+pub fn is_binary_text(text: &str) -> bool {
+    text.chars().take(SNIFF_LENGTH).any(|c| c == '\0')
+}
+
+/// Builds the metadata stub for a binary file.
+///
+/// # Arguments
+///
+/// * `file_path` - Path to the file, used for extension-based MIME guessing.
+/// * `bytes` - The raw file content.
+///
+/// # Returns
+///
+/// * `BinaryInfo` - Size, MIME type guess and hash of the file.
+pub fn binary_info(file_path: &Path, bytes: &[u8]) -> BinaryInfo {
+    BinaryInfo {
+        size: bytes.len() as u64,
+        mime_type: guess_mime_type(file_path, bytes),
+        sha256: sha256_hex(bytes),
+    }
+}
+
+/// Returns the hex-encoded SHA-256 hash of the given bytes.
+pub fn sha256_hex(bytes: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(bytes))
+}
+
+/// Guesses the MIME type of a binary file from its magic number, then its extension.
+fn guess_mime_type(file_path: &Path, bytes: &[u8]) -> &'static str {
+    if let Some(mime_type) = magic_mime_type(bytes) {
+        // ZIP containers are refined by extension (e.g., `.jar`)
+        if mime_type != "application/zip" {
+            return mime_type;
+        }
+    }
+
+    let extension = file_path
+        .extension()
+        .unwrap_or_default()
+        .to_string_lossy()
+        .to_lowercase();
+    EXTENSION_MIME_TYPES
+        .iter()
+        .find(|(ext, _)| *ext == extension)
+        .map(|(_, mime_type)| *mime_type)
+        .or_else(|| magic_mime_type(bytes))
+        .unwrap_or("application/octet-stream")
+}
+
+/// Returns the MIME type of the first magic number matching the given bytes.
+fn magic_mime_type(bytes: &[u8]) -> Option<&'static str> {
+    if bytes.len() >= 12 && &bytes[..4] == b"RIFF" && &bytes[8..12] == b"WEBP" {
+        return Some("image/webp");
+    }
+    MAGIC_NUMBERS
+        .iter()
+        .find(|(magic, _)| bytes.starts_with(magic))
+        .map(|(_, mime_type)| *mime_type)
+}
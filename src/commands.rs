@@ -0,0 +1,499 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use anyhow::{Context, Result};
+use serde::Serialize;
+use crate::config::Config;
+use crate::project_options::{file_stem_for, ProjectOptions};
+use crate::{file_processor, tree_generator};
+
+/// Top-level directories left out of scaffolded configurations, usually build output
+/// or dependencies.
+const SKIPPED_DIRECTORIES: &[&str] = &[
+    "target", "node_modules", "build", "dist", "out", "bin", "obj", "vendor", "venv",
+    "__pycache__", "output", "logs",
+];
+
+/// Project configuration written by [`init`].
+#[derive(Debug, Serialize)]
+struct ConfigTemplate {
+    project_name: String,
+    project_path: PathBuf,
+    output_file: String,
+    files: Vec<String>,
+    directories: Vec<String>,
+}
+
+/// Returns the name of a project configuration: its file name without extension.
+pub fn config_name(config_path: &Path) -> String {
+    config_path.file_stem().unwrap_or_default().to_string_lossy().into_owned()
+}
+
+/// A project to process: a configuration file, or a directory configured from the
+/// command line.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Project {
+    /// A project configuration file.
+    Config(PathBuf),
+    /// A project directory without configuration file.
+    Directory(PathBuf),
+}
+
+impl Project {
+    /// Returns the name shown while processing the project.
+    pub fn name(&self) -> String {
+        match self {
+            Project::Config(config_path) => config_name(config_path),
+            Project::Directory(project_dir) => project_dir.display().to_string(),
+        }
+    }
+
+    /// Loads and validates the configuration of the project.
+    ///
+    /// # Arguments
+    ///
+    /// * `options` - Project options given on the command line, for a directory.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Config>` - The configuration or an error.
+    pub fn load(&self, options: &ProjectOptions) -> Result<Config> {
+        match self {
+            Project::Config(config_path) => Config::load(config_path)
+                .context(format!("Failed to load configuration: {}", config_path.display())),
+            Project::Directory(project_dir) => options.to_config(project_dir),
+        }
+    }
+}
+
+/// Returns the selected projects.
+///
+/// A selector written as a path (`./repo`, `../repo`, `repo/`, or any selector with a
+/// path separator) selects that directory as a project configured by the command line
+/// options. A plain name selects the matching configurations as in
+/// [`select_config_files`], and only selects a directory of that name if no
+/// configuration matches it. When only directories are given, no configuration is
+/// selected.
+///
+/// # Arguments
+///
+/// * `projects_dir` - Directory of the project configurations.
+/// * `selectors` - Directories, names or glob patterns of the projects to select.
+/// * `config_files` - Explicit configuration files, which may be outside `projects_dir`.
+/// * `options` - Project options given on the command line.
+///
+/// # Returns
+///
+/// * `Result<Vec<Project>>` - The selected configurations, followed by the directories.
+pub fn select_projects(
+    projects_dir: &Path,
+    selectors: &[String],
+    config_files: &[PathBuf],
+    options: &ProjectOptions,
+) -> Result<Vec<Project>> {
+    select_projects_in(Path::new("."), projects_dir, selectors, config_files, options)
+}
+
+/// Returns the selected projects, resolving directory selectors against `working_dir`.
+///
+/// See [`select_projects`]; the returned directories are the selectors as given.
+fn select_projects_in(
+    working_dir: &Path,
+    projects_dir: &Path,
+    selectors: &[String],
+    config_files: &[PathBuf],
+    options: &ProjectOptions,
+) -> Result<Vec<Project>> {
+    let (directories, selectors): (Vec<&String>, Vec<&String>) = selectors
+        .iter()
+        .partition(|selector| is_directory_selector(working_dir, projects_dir, selector));
+    let selectors: Vec<String> = selectors.into_iter().cloned().collect();
+
+    if directories.is_empty() && !options.is_empty() {
+        anyhow::bail!("Project options require a project directory (e.g., `generate ./repo --include 'src/**'`)");
+    }
+    if directories.len() > 1 && (options.name.is_some() || options.output.is_some()) {
+        anyhow::bail!("--name and --output cannot be used with more than one project directory");
+    }
+
+    let mut projects = Vec::new();
+    if directories.is_empty() || !selectors.is_empty() || !config_files.is_empty() {
+        projects.extend(select_config_files(projects_dir, &selectors, config_files)?.into_iter().map(Project::Config));
+    }
+    projects.extend(directories.into_iter().map(|directory| Project::Directory(PathBuf::from(directory))));
+    Ok(projects)
+}
+
+/// Returns `true` if a selector selects a project directory rather than configurations.
+fn is_directory_selector(working_dir: &Path, projects_dir: &Path, selector: &str) -> bool {
+    if !working_dir.join(selector).is_dir() {
+        return false;
+    }
+    let is_path = selector.contains(['/', std::path::MAIN_SEPARATOR]) || selector == "." || selector == "..";
+    is_path || select_config_files(projects_dir, &[selector.to_string()], &[]).is_err()
+}
+
+/// Returns the configuration files of the selected projects.
+///
+/// Without selectors or explicit configuration files, every configuration in the
+/// projects directory is selected. Otherwise the explicit configuration files are
+/// selected, followed by the configurations in the projects directory that match a
+/// selector. A selector matches a configuration by its file name (with or without
+/// `.yml`) or by its `project_name`; a selector containing `*`, `?` or `[` is a glob
+/// pattern matched against both. Every selector must match at least one project.
+///
+/// # Arguments
+///
+/// * `projects_dir` - Directory of the project configurations.
+/// * `selectors` - Names or glob patterns of the projects to select.
+/// * `config_files` - Explicit configuration files, which may be outside `projects_dir`.
+///
+/// # Returns
+///
+/// * `Result<Vec<PathBuf>>` - The selected configuration files, without duplicates.
+pub fn select_config_files(projects_dir: &Path, selectors: &[String], config_files: &[PathBuf]) -> Result<Vec<PathBuf>> {
+    let mut selected: Vec<PathBuf> = Vec::new();
+    for config_path in config_files {
+        if !config_path.is_file() {
+            anyhow::bail!("Configuration file not found: {}", config_path.display());
+        }
+        if !selected.contains(config_path) {
+            selected.push(config_path.clone());
+        }
+    }
+
+    let available = get_config_files(projects_dir)?;
+    if selectors.is_empty() {
+        return Ok(if config_files.is_empty() { available } else { selected });
+    }
+
+    // Project names are only known once the configurations are read
+    let project_names: Vec<Option<String>> = available
+        .iter()
+        .map(|config_path| Config::parse(config_path).ok().map(|config| config.project_name))
+        .collect();
+
+    for selector in selectors {
+        let name = selector.strip_suffix(".yml").unwrap_or(selector);
+        let pattern = if name.contains(['*', '?', '[']) {
+            Some(glob::Pattern::new(name).context(format!("Invalid project pattern: {}", selector))?)
+        } else {
+            None
+        };
+        let matches = |candidate: &str| pattern.as_ref().map_or(candidate == name, |pattern| pattern.matches(candidate));
+
+        let mut matched = false;
+        for (config_path, project_name) in available.iter().zip(&project_names) {
+            if matches(&config_name(config_path)) || project_name.as_deref().is_some_and(matches) {
+                matched = true;
+                if !selected.contains(config_path) {
+                    selected.push(config_path.clone());
+                }
+            }
+        }
+        if !matched {
+            anyhow::bail!("Unknown project: {} (no configuration in {} matches it)", selector, projects_dir.display());
+        }
+    }
+    Ok(selected)
+}
+
+/// Returns all YAML configuration files of the projects directory, sorted by path.
+pub fn get_config_files(projects_dir: &Path) -> Result<Vec<PathBuf>> {
+    let mut config_files = Vec::new();
+    
+    if !projects_dir.exists() {
+        return Ok(config_files);
+    }
+
+    for entry in fs::read_dir(projects_dir)
+        .context(format!("Failed to read projects directory: {}", projects_dir.display()))? {
+        let entry = entry.context("Failed to read directory entry")?;
+        let config_path = entry.path();
+        
+        if config_path.is_file() && config_path.extension().unwrap_or_default() == "yml" {
+            config_files.push(config_path);
+        }
+    }
+
+    // Sort for consistent ordering
+    config_files.sort();
+    Ok(config_files)
+}
+
+/// Prints the project configurations with their resolved paths.
+///
+/// Each configuration is printed on one tab-separated line: its name, the
+/// configuration file, the project directory and the output file. Configurations that
+/// cannot be read are printed with their error instead of the paths.
+///
+/// # Arguments
+///
+/// * `config_files` - Paths to the project configuration files.
+/// * `output_dir` - Output directory for generated documentation.
+///
+/// # Returns
+///
+/// * `Result<()>` - Success or error.
+pub fn list(config_files: &[PathBuf], output_dir: &Path) -> Result<()> {
+    for config_path in config_files {
+        let name = config_name(config_path);
+        match Config::parse(config_path) {
+            Ok(config) => {
+                let project_path = fs::canonicalize(&config.project_path).unwrap_or(config.project_path);
+                println!(
+                    "{}\t{}\t{}\t{}",
+                    name,
+                    config_path.display(),
+                    project_path.display(),
+                    output_dir.join(&config.output_file).display()
+                );
+            }
+            Err(err) => println!("{}\t{}\terror: {:#}", name, config_path.display(), err),
+        }
+    }
+    Ok(())
+}
+
+/// Checks the project configurations and prints every problem found.
+///
+/// Problems are printed one per line, prefixed with the configuration file.
+///
+/// # Arguments
+///
+/// * `config_files` - Paths to the project configuration files.
+///
+/// # Returns
+///
+/// * `Result<()>` - Success, or an error if any configuration is invalid.
+pub fn validate(config_files: &[PathBuf]) -> Result<()> {
+    let mut invalid = 0;
+    for config_path in config_files {
+        let problems = match Config::parse(config_path) {
+            Ok(config) => config.problems(),
+            Err(err) => vec![format!("{:#}", err)],
+        };
+        if problems.is_empty() {
+            println!("{}: ok", config_path.display());
+            continue;
+        }
+        invalid += 1;
+        for problem in problems {
+            println!("{}: {}", config_path.display(), problem);
+        }
+    }
+
+    if invalid > 0 {
+        anyhow::bail!("{} of {} project configurations are invalid", invalid, config_files.len());
+    }
+    Ok(())
+}
+
+/// Writes a project configuration for a directory into the projects directory.
+///
+/// The configuration lists the files and directories at the top of the project,
+/// leaving out hidden entries and common build output directories.
+///
+/// # Arguments
+///
+/// * `project_dir` - Directory of the project.
+/// * `name` - Name of the project; defaults to the name of the directory.
+/// * `projects_dir` - Directory the configuration is written to.
+/// * `force` - Whether an existing configuration is overwritten.
+///
+/// # Returns
+///
+/// * `Result<PathBuf>` - The path of the written configuration or an error.
+pub fn init(project_dir: &Path, name: Option<&str>, projects_dir: &Path, force: bool) -> Result<PathBuf> {
+    let project_path = fs::canonicalize(project_dir)
+        .context(format!("Project directory does not exist: {}", project_dir.display()))?;
+    if !project_path.is_dir() {
+        anyhow::bail!("Project path is not a directory: {}", project_path.display());
+    }
+    let project_name = match name {
+        Some(name) => name.to_string(),
+        None => project_path.file_name().unwrap_or_default().to_string_lossy().into_owned(),
+    };
+    let file_stem = file_stem_for(&project_name);
+    if file_stem.is_empty() {
+        anyhow::bail!("Cannot derive a configuration file name from project name: {}", project_name);
+    }
+
+    let config_path = projects_dir.join(format!("{}.yml", file_stem));
+    if config_path.exists() && !force {
+        anyhow::bail!("Configuration already exists: {} (use --force to overwrite)", config_path.display());
+    }
+
+    // List the top-level entries of the project
+    let mut files = Vec::new();
+    let mut directories = Vec::new();
+    for entry in fs::read_dir(&project_path)
+        .context(format!("Failed to read project directory: {}", project_path.display()))? {
+        let entry = entry.context("Failed to read directory entry")?;
+        let file_name = entry.file_name().to_string_lossy().into_owned();
+        if file_name.starts_with('.') {
+            continue;
+        }
+        let path = entry.path();
+        if path.is_dir() {
+            if !SKIPPED_DIRECTORIES.contains(&file_name.as_str()) {
+                directories.push(file_name);
+            }
+        } else if path.is_file() {
+            files.push(file_name);
+        }
+    }
+    files.sort();
+    directories.sort();
+
+    let template = ConfigTemplate {
+        project_name,
+        project_path,
+        output_file: format!("{}.md", file_stem),
+        files,
+        directories,
+    };
+    let yaml = serde_yaml::to_string(&template).context("Failed to serialize project configuration")?;
+
+    fs::create_dir_all(projects_dir)
+        .context(format!("Failed to create projects directory: {}", projects_dir.display()))?;
+    fs::write(&config_path, yaml)
+        .context(format!("Failed to write configuration file: {}", config_path.display()))?;
+    Ok(config_path)
+}
+
+/// Prints the tree and the list of files each project would document, without
+/// reading the files.
+///
+/// Files are selected with the same rules as during generation, except for the
+/// content budget, which depends on the file contents.
+///
+/// # Arguments
+///
+/// * `projects` - The projects to preview.
+/// * `options` - Project options given on the command line, for directories.
+///
+/// # Returns
+///
+/// * `Result<()>` - Success or error.
+pub fn preview(projects: &[Project], options: &ProjectOptions) -> Result<()> {
+    for (index, project) in projects.iter().enumerate() {
+        let config = project.load(options)?;
+        let files = file_processor::select_files(&config)
+            .context(format!("Failed to select files for project: {}", config.project_name))?;
+
+        if index > 0 {
+            println!();
+        }
+        println!("{}", config.project_name);
+        print!("{}", tree_generator::generate_path_tree(&config.project_name, &files, &config.project_path)?);
+        println!();
+        for file_path in &files {
+            let relative_path = file_path.strip_prefix(&config.project_path).unwrap_or(file_path);
+            match config.line_range(file_path) {
+                Some(lines) => println!("{}#L{}-{}", relative_path.display(), lines.start, lines.end),
+                None => println!("{}", relative_path.display()),
+            }
+        }
+        println!("{} files", files.len());
+        if let Some(range) = &config.diff {
+            println!("Only files changed between {} and {} are documented", range.from, range.to);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_select_config_files() {
+        let temp_dir = TempDir::new().unwrap();
+        let projects_dir = temp_dir.path().join("projects");
+        fs::create_dir(&projects_dir).unwrap();
+        let write_config = |path: &Path, project_name: &str| {
+            fs::write(path, format!(
+                "project_name: {}\nproject_path: .\noutput_file: out.md\nfiles: []\ndirectories: []\n",
+                project_name
+            )).unwrap();
+        };
+        write_config(&projects_dir.join("alpha.yml"), "Alpha Service");
+        write_config(&projects_dir.join("web-api.yml"), "API");
+        write_config(&projects_dir.join("web-ui.yml"), "UI");
+        let external = temp_dir.path().join("external.yml");
+        write_config(&external, "External");
+
+        let select = |selectors: &[&str], config_files: &[PathBuf]| {
+            let selectors: Vec<String> = selectors.iter().map(|s| s.to_string()).collect();
+            select_config_files(&projects_dir, &selectors, config_files)
+        };
+        let names = |paths: Vec<PathBuf>| paths.iter().map(|path| config_name(path)).collect::<Vec<_>>();
+
+        assert_eq!(names(select(&[], &[]).unwrap()), ["alpha", "web-api", "web-ui"]);
+        assert_eq!(names(select(&["web-ui.yml", "Alpha Service"], &[]).unwrap()), ["web-ui", "alpha"]);
+        assert_eq!(names(select(&["web-*", "UI"], &[]).unwrap()), ["web-api", "web-ui"]);
+        assert_eq!(names(select(&[], std::slice::from_ref(&external)).unwrap()), ["external"]);
+        assert_eq!(names(select(&["alpha"], &[external]).unwrap()), ["external", "alpha"]);
+        assert!(select(&["beta"], &[]).is_err());
+        assert!(select(&[], &[temp_dir.path().join("missing.yml")]).is_err());
+    }
+
+    #[test]
+    fn test_select_projects() {
+        // A `src` directory next to a `src.yml` configuration
+        let temp_dir = TempDir::new().unwrap();
+        let working_dir = temp_dir.path();
+        let projects_dir = working_dir.join("projects");
+        fs::create_dir(&projects_dir).unwrap();
+        fs::create_dir(working_dir.join("src")).unwrap();
+        let src_config = projects_dir.join("src.yml");
+        fs::write(
+            &src_config,
+            "project_name: Sources\nproject_path: .\noutput_file: src.md\nfiles: []\ndirectories: []\n",
+        ).unwrap();
+
+        let select = |selectors: &[&str], options: &ProjectOptions| {
+            let selectors: Vec<String> = selectors.iter().map(|s| s.to_string()).collect();
+            select_projects_in(working_dir, &projects_dir, &selectors, &[], options)
+        };
+        let options = ProjectOptions::default();
+        let with_options = ProjectOptions { include_patterns: vec!["**".to_string()], ..ProjectOptions::default() };
+        let directory = |path: &str| Project::Directory(PathBuf::from(path));
+
+        // A plain name prefers the configuration, a path selects the directory
+        assert_eq!(select(&["src"], &options).unwrap(), [Project::Config(src_config.clone())]);
+        assert!(select(&["src"], &with_options).is_err());
+        assert_eq!(select(&["./src"], &with_options).unwrap(), [directory("./src")]);
+        assert_eq!(select(&["src/"], &options).unwrap(), [directory("src/")]);
+        assert_eq!(select(&["Sources", "."], &options).unwrap(), [Project::Config(src_config), directory(".")]);
+
+        // A plain name without configuration selects the directory
+        fs::remove_file(projects_dir.join("src.yml")).unwrap();
+        assert_eq!(select(&["src"], &with_options).unwrap(), [directory("src")]);
+        assert!(select(&["src", "."], &ProjectOptions { name: Some("x".to_string()), ..options }).is_err());
+    }
+
+    #[test]
+    fn test_init() {
+        let temp_dir = TempDir::new().unwrap();
+        let project_dir = temp_dir.path().join("demo");
+        fs::create_dir_all(project_dir.join("src")).unwrap();
+        fs::create_dir_all(project_dir.join("target")).unwrap();
+        fs::create_dir_all(project_dir.join(".git")).unwrap();
+        fs::write(project_dir.join("Cargo.toml"), "[package]").unwrap();
+        let projects_dir = temp_dir.path().join("projects");
+
+        let config_path = init(&project_dir, Some("My Demo"), &projects_dir, false).unwrap();
+        assert_eq!(config_path, projects_dir.join("my_demo.yml"));
+        let config = Config::load(&config_path).unwrap();
+        assert_eq!(config.project_name, "My Demo");
+        assert_eq!(config.output_file, PathBuf::from("my_demo.md"));
+        assert_eq!(config.files[0].path(), Path::new("Cargo.toml"));
+        assert_eq!(config.directories.len(), 1);
+        assert_eq!(config.directories[0].path(), Path::new("src"));
+
+        assert!(init(&project_dir, Some("My Demo"), &projects_dir, false).is_err());
+        assert!(init(&project_dir, Some("My Demo"), &projects_dir, true).is_ok());
+    }
+}
@@ -1,14 +1,15 @@
 use serde::Deserialize;
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use anyhow::{Context, Result};
+use crate::encoding_detector;
 
 /// Configuration structure for a project.
 ///
 /// This struct represents the configuration for a project, including its name,
 /// root directory path, output file path, specific files to include, directories
 /// to include recursively, and directories to exclude.
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Deserialize)]
 pub struct Config {
     /// Name of the project.
     pub project_name: String,
@@ -16,10 +17,21 @@ pub struct Config {
     pub project_path: PathBuf,
     /// Path to the output file where the documentation will be saved.
     pub output_file: PathBuf,
-    /// List of specific files to include in the documentation.
-    pub files: Vec<PathBuf>,
+    /// Format of the generated documentation. If not specified, it is inferred from the
+    /// extension of `output_file`.
+    #[serde(default)]
+    pub output_format: Option<OutputFormat>,
+    /// List of specific files to include in the documentation. An entry may end with a
+    /// line range (e.g., "src/main.rs#L10-80") to include only an excerpt of the file.
+    pub files: Vec<FileEntry>,
     /// List of directories to include in the documentation (files within these directories will be processed recursively).
-    pub directories: Vec<PathBuf>,
+    pub directories: Vec<DirectoryEntry>,
+    /// List of glob patterns a file found in `directories` must match to be included
+    /// (e.g., "src/**/*.rs"). Evaluated before `exclude_patterns`. `*` and `?` never match
+    /// `/`, so "*.rs" only matches top-level files; use `**` to cross directories. If empty,
+    /// every file is a candidate.
+    #[serde(default)]
+    pub include_patterns: Vec<String>,
     /// List of directories to exclude from the documentation.
     #[serde(default)]
     pub exclude_directories: Vec<String>,
@@ -30,16 +42,318 @@ pub struct Config {
     /// If not specified, no limit is applied.
     #[serde(default)]
     pub max_file_size: Option<u64>,
+    /// Whether `.gitignore`, `.ignore` and `.mdignore` files are honored when walking
+    /// `directories`. Defaults to `true`.
+    #[serde(default = "default_respect_ignore_files")]
+    pub respect_ignore_files: bool,
+    /// Restricts files found in `directories` to a selection from the project's git
+    /// repository (e.g., `tracked`, `working_tree` or `changed_since: main`). Files
+    /// listed in `files` are not affected.
+    #[serde(default)]
+    pub git_filter: Option<GitFilter>,
+    /// Whether the Markdown header describes the repository revision (branch, `HEAD`
+    /// commit, working tree status and generation time), and each file heading names
+    /// the author and date of the file's last commit.
+    #[serde(default)]
+    pub git_metadata: bool,
+    /// Two revisions of the project's repository to compare. When set, the output is a
+    /// diff document (the tree of changed files and their unified diffs) instead of a
+    /// snapshot of file contents.
+    #[serde(default)]
+    pub diff: Option<DiffRange>,
+
+    /// Encoding of source files that are not valid UTF-8 (e.g., "gbk", "shift_jis", "utf-16le").
+    /// If not specified, the encoding is detected automatically. Files with a byte order
+    /// mark always use the encoding of the BOM, and a UTF-16 encoding applies to every
+    /// file, since UTF-16 text can also be valid UTF-8.
+    #[serde(default)]
+    pub source_encoding: Option<String>,
+    /// Per-glob encoding overrides, checked in order before `source_encoding`.
+    #[serde(default)]
+    pub encoding_overrides: Vec<EncodingOverride>,
+
+    /// Whether each line of a file is prefixed with its line number in the Markdown output.
+    #[serde(default)]
+    pub line_numbers: bool,
+    /// Whether a linked table of contents of all files is generated. Defaults to `true`.
+    #[serde(default = "default_table_of_contents")]
+    pub table_of_contents: bool,
+    /// Whether the file tree is rendered as a nested list linking each file to its
+    /// section, instead of a plain text block.
+    #[serde(default)]
+    pub linked_tree: bool,
+
+    /// Encoding used to count tokens (e.g., "cl100k_base", "o200k_base", or "estimate" for
+    /// a character-based approximation). Defaults to "cl100k_base".
+    #[serde(default)]
+    pub token_encoding: TokenEncoding,
+    /// Whether a table of per-file token counts is added at the top of the Markdown output.
+    #[serde(default)]
+    pub token_summary: bool,
+    /// Whether a table of file, line and byte counts per language is added to the
+    /// Markdown output.
+    #[serde(default)]
+    pub statistics: bool,
+    /// Maximum number of tokens of file content in the whole documentation. When the
+    /// files exceed it, the most important ones (see `priority`) are kept.
+    #[serde(default)]
+    pub max_total_tokens: Option<usize>,
+    /// Maximum number of bytes of file content in the whole documentation, like
+    /// `max_total_tokens`.
+    #[serde(default)]
+    pub max_total_bytes: Option<u64>,
+    /// Glob patterns ordering files by importance when a total budget is set (e.g.,
+    /// "src/**", "Cargo.toml"). Files matching no pattern come last.
+    #[serde(default)]
+    pub priority: Vec<String>,
+    /// Maximum number of tokens per output part. When set, Markdown output larger than
+    /// this is split into `name.part1.md`, `name.part2.md`, ... plus an index file.
+    #[serde(default)]
+    pub max_tokens_per_part: Option<usize>,
+    /// Maximum number of bytes per output part, like `max_tokens_per_part`.
+    #[serde(default)]
+    pub max_bytes_per_part: Option<u64>,
 
     /// Markdown output language. "zh_cn" for Chinese, "en_us" for English (default).
     #[serde(default = "default_markdown_lang")]
     pub markdown_lang: String,
 }
 
+/// Format of the generated documentation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputFormat {
+    /// Markdown document.
+    Markdown,
+    /// Self-contained HTML page.
+    Html,
+    /// JSON document with the project tree and one record per file.
+    Json,
+    /// JSON Lines: a project record followed by one record per file.
+    Jsonl,
+    /// XML-tagged document intended for LLM prompts.
+    Xml,
+}
+
+/// Tokenizer encoding used to estimate the size of the documentation.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TokenEncoding {
+    /// BPE used by GPT-4 and GPT-3.5 models.
+    #[default]
+    Cl100kBase,
+    /// BPE used by GPT-4o models.
+    O200kBase,
+    /// BPE used by Codex models.
+    P50kBase,
+    /// BPE used by GPT-3 models.
+    R50kBase,
+    /// Character-based estimate, about four characters per token.
+    Estimate,
+}
+
+impl TokenEncoding {
+    /// Returns the name of the encoding, as written in configuration files.
+    pub fn name(&self) -> &'static str {
+        match self {
+            TokenEncoding::Cl100kBase => "cl100k_base",
+            TokenEncoding::O200kBase => "o200k_base",
+            TokenEncoding::P50kBase => "p50k_base",
+            TokenEncoding::R50kBase => "r50k_base",
+            TokenEncoding::Estimate => "estimate",
+        }
+    }
+}
+
+/// Selection of files from the project's git repository.
+///
+/// Either a mode name (`tracked` or `working_tree`), or a mapping with a
+/// `changed_since` ref or commit.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(untagged)]
+pub enum GitFilter {
+    /// A selection that needs no argument.
+    Mode(GitMode),
+    /// Files changed since a ref or commit (e.g., "main", "v1.2.0", "HEAD~3"), including
+    /// uncommitted and untracked changes. Changes are taken from the merge base of the
+    /// ref and `HEAD`, so only what the current branch touched is selected.
+    ChangedSince { changed_since: String },
+}
+
+/// Git selections that need no argument.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GitMode {
+    /// Files tracked in the git index.
+    Tracked,
+    /// Files with staged or unstaged changes, and untracked files.
+    WorkingTree,
+}
+
+/// Revisions compared by a diff document.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct DiffRange {
+    /// Old revision: a ref or commit (e.g., "main", "v1.2.0", "HEAD~3").
+    pub from: String,
+    /// New revision, like `from`. Defaults to "HEAD".
+    #[serde(default = "default_diff_to")]
+    pub to: String,
+}
+
+/// A file to include in the documentation.
+///
+/// Written as a path relative to the project root, optionally followed by a line range:
+/// `src/main.rs#L10-80` (lines 10 to 80), `src/main.rs#L10-L80` or `src/main.rs#L42`
+/// (a single line).
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(try_from = "String")]
+pub struct FileEntry {
+    /// Path of the file, relative to the project root.
+    path: PathBuf,
+    /// Lines to include, or `None` for the whole file.
+    lines: Option<LineRange>,
+}
+
+impl FileEntry {
+    /// Returns the path of the file, relative to the project root.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Returns the lines to include, or `None` for the whole file.
+    pub fn lines(&self) -> Option<LineRange> {
+        self.lines
+    }
+}
+
+impl TryFrom<String> for FileEntry {
+    type Error = anyhow::Error;
+
+    fn try_from(entry: String) -> Result<Self> {
+        // A `#` not followed by a line range is part of the file name
+        if let Some((path, fragment)) = entry.rsplit_once('#') {
+            if let Some(lines) = LineRange::parse(fragment)? {
+                return Ok(FileEntry { path: PathBuf::from(path), lines: Some(lines) });
+            }
+        }
+        Ok(FileEntry { path: PathBuf::from(entry), lines: None })
+    }
+}
+
+/// An inclusive range of one-based line numbers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LineRange {
+    /// First line of the range.
+    pub start: usize,
+    /// Last line of the range.
+    pub end: usize,
+}
+
+impl LineRange {
+    /// Parses a line range fragment such as "L10-80", "L10-L80" or "L42".
+    ///
+    /// Returns `Ok(None)` if the fragment is not a line range, and an error if it is an
+    /// invalid one (e.g., "L0" or "L80-10").
+    fn parse(fragment: &str) -> Result<Option<Self>> {
+        let Some(range) = fragment.strip_prefix('L') else { return Ok(None) };
+        let (start, end) = match range.split_once('-') {
+            Some((start, end)) => (start, end.strip_prefix('L').unwrap_or(end)),
+            None => (range, range),
+        };
+        let is_number = |s: &str| !s.is_empty() && s.bytes().all(|b| b.is_ascii_digit());
+        if !is_number(start) || !is_number(end) {
+            return Ok(None);
+        }
+
+        let start: usize = start.parse().context(format!("Invalid line range: #{}", fragment))?;
+        let end: usize = end.parse().context(format!("Invalid line range: #{}", fragment))?;
+        if start == 0 || end < start {
+            anyhow::bail!("Invalid line range: #{}", fragment);
+        }
+        Ok(Some(LineRange { start, end }))
+    }
+}
+
+/// A directory to include in the documentation.
+///
+/// Either a plain path, or a mapping with a `path` and its own `include_patterns`.
+/// Scoped patterns are matched against paths relative to the directory itself and take
+/// precedence over the project-wide `include_patterns`.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+pub enum DirectoryEntry {
+    /// A directory whose files are filtered by the project-wide patterns.
+    Path(PathBuf),
+    /// A directory with its own include patterns.
+    Scoped {
+        /// Path of the directory, relative to the project root.
+        path: PathBuf,
+        /// Glob patterns relative to `path` (e.g., "**/*.rs").
+        #[serde(default)]
+        include_patterns: Vec<String>,
+    },
+}
+
+impl DirectoryEntry {
+    /// Returns the path of the directory, relative to the project root.
+    pub fn path(&self) -> &Path {
+        match self {
+            DirectoryEntry::Path(path) => path,
+            DirectoryEntry::Scoped { path, .. } => path,
+        }
+    }
+
+    /// Returns the include patterns scoped to this directory, if any.
+    pub fn include_patterns(&self) -> &[String] {
+        match self {
+            DirectoryEntry::Path(_) => &[],
+            DirectoryEntry::Scoped { include_patterns, .. } => include_patterns,
+        }
+    }
+}
+
+/// An encoding applied to the files matching a glob pattern.
+#[derive(Debug, Clone, Deserialize)]
+pub struct EncodingOverride {
+    /// Glob pattern matched against paths relative to the project root (e.g., "legacy/**/*.c").
+    pub pattern: String,
+    /// Encoding label (e.g., "gbk").
+    pub encoding: String,
+}
+
 fn default_markdown_lang() -> String {
     "en_us".to_string()
 }
 
+fn default_respect_ignore_files() -> bool {
+    true
+}
+
+fn default_table_of_contents() -> bool {
+    true
+}
+
+fn default_diff_to() -> String {
+    "HEAD".to_string()
+}
+
+/// Builds the configuration of a project "Demo" in `/project` for tests.
+///
+/// # Arguments
+///
+/// * `extra` - YAML fields added to, or replacing, the required ones.
+#[cfg(test)]
+pub fn test_config(extra: &str) -> Config {
+    let mut fields: serde_yaml::Mapping = serde_yaml::from_str(
+        "project_name: Demo\nproject_path: /project\noutput_file: demo.md\nfiles: []\ndirectories: []\n",
+    ).unwrap();
+    if !extra.trim().is_empty() {
+        let extra: serde_yaml::Mapping = serde_yaml::from_str(extra).unwrap();
+        fields.extend(extra);
+    }
+    serde_yaml::from_value(serde_yaml::Value::Mapping(fields)).unwrap()
+}
+
 impl Config {
     /// Loads a project configuration from a YAML file.
     ///
@@ -50,13 +364,9 @@ impl Config {
     /// # Returns
     ///
     /// * `Result<Self>` - The loaded configuration or an error.
-    pub fn load(config_path: &PathBuf) -> Result<Self> {
-        let config_content = fs::read_to_string(config_path)
-            .context(format!("Failed to read configuration file: {}", config_path.display()))?;
-        
-        let config: Config = serde_yaml::from_str(&config_content)
-            .context(format!("Failed to parse configuration file: {}", config_path.display()))?;
-        
+    pub fn load(config_path: &Path) -> Result<Self> {
+        let config = Config::parse(config_path)?;
+
         // Validate configuration
         config.validate()
             .context(format!("Invalid configuration in: {}", config_path.display()))?;
@@ -64,59 +374,242 @@ impl Config {
         Ok(config)
     }
 
+    /// Reads a project configuration from a YAML file without validating its values.
+    ///
+    /// # Arguments
+    ///
+    /// * `config_path` - Path to the YAML configuration file.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Self>` - The parsed configuration or an error.
+    pub fn parse(config_path: &Path) -> Result<Self> {
+        let config_content = fs::read_to_string(config_path)
+            .context(format!("Failed to read configuration file: {}", config_path.display()))?;
+        
+        serde_yaml::from_str(&config_content)
+            .context(format!("Failed to parse configuration file: {}", config_path.display()))
+    }
+
+    /// Builds a project configuration from YAML values, validating it like a file.
+    ///
+    /// # Arguments
+    ///
+    /// * `value` - A YAML mapping with the fields of a configuration file.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Self>` - The validated configuration or an error.
+    pub fn from_value(value: serde_yaml::Value) -> Result<Self> {
+        let config: Config = serde_yaml::from_value(value)
+            .context("Failed to parse configuration")?;
+        config.validate()
+    }
+
+    /// Returns the lines of a file to include, or `None` for the whole file.
+    ///
+    /// # Arguments
+    ///
+    /// * `file_path` - Path to the file, under the project root.
+    pub fn line_range(&self, file_path: &Path) -> Option<LineRange> {
+        self.files
+            .iter()
+            .find(|file| self.project_path.join(file.path()) == file_path)
+            .and_then(FileEntry::lines)
+    }
+
+    /// Returns the output format, inferring it from the output file extension if needed.
+    pub fn output_format(&self) -> OutputFormat {
+        if let Some(format) = self.output_format {
+            return format;
+        }
+        let extension = self.output_file
+            .extension()
+            .unwrap_or_default()
+            .to_string_lossy()
+            .to_lowercase();
+        match extension.as_str() {
+            "html" | "htm" => OutputFormat::Html,
+            "json" => OutputFormat::Json,
+            "jsonl" => OutputFormat::Jsonl,
+            "xml" => OutputFormat::Xml,
+            _ => OutputFormat::Markdown,
+        }
+    }
+
     /// Validates the configuration values.
+    ///
+    /// Fails with every problem found, one per line.
     fn validate(&self) -> Result<Self> {
+        let problems = self.problems();
+        if !problems.is_empty() {
+            anyhow::bail!("{}", problems.join("\n"));
+        }
+        Ok(self.clone())
+    }
+
+    /// Returns every problem found in the configuration values.
+    pub fn problems(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+
         // Validate project name
         if self.project_name.trim().is_empty() {
-            anyhow::bail!("Project name cannot be empty");
+            problems.push("Project name cannot be empty".to_string());
         }
 
         // Validate project path
         if !self.project_path.exists() {
-            anyhow::bail!("Project path does not exist: {}", self.project_path.display());
-        }
-        if !self.project_path.is_dir() {
-            anyhow::bail!("Project path is not a directory: {}", self.project_path.display());
+            problems.push(format!("Project path does not exist: {}", self.project_path.display()));
+        } else if !self.project_path.is_dir() {
+            problems.push(format!("Project path is not a directory: {}", self.project_path.display()));
         }
 
         // Validate output file extension
         if let Some(ext) = self.output_file.extension() {
             let ext_str = ext.to_string_lossy().to_lowercase();
-            if !["md", "markdown", "html", "txt"].contains(&ext_str.as_str()) {
+            if !["md", "markdown", "html", "htm", "json", "jsonl", "xml", "txt"].contains(&ext_str.as_str()) {
                 log::warn!("Output file extension '{}' might not be supported", ext_str);
             }
         }
 
         // Validate max file size
-        if let Some(max_size) = self.max_file_size {
-            if max_size == 0 {
-                anyhow::bail!("Max file size cannot be zero");
+        if self.max_file_size == Some(0) {
+            problems.push("Max file size cannot be zero".to_string());
+        }
+
+        // Validate the git filter
+        if let Some(GitFilter::ChangedSince { changed_since }) = &self.git_filter {
+            if changed_since.trim().is_empty() {
+                problems.push("git_filter changed_since requires a ref or commit".to_string());
+            }
+        }
+
+        // Validate the diff range
+        if let Some(range) = &self.diff {
+            if range.from.trim().is_empty() || range.to.trim().is_empty() {
+                problems.push("diff requires a ref or commit for both from and to".to_string());
+            }
+            if self.output_format() != OutputFormat::Markdown {
+                problems.push("Diff documents are only supported for Markdown output".to_string());
+            }
+            if self.max_tokens_per_part.is_some() || self.max_bytes_per_part.is_some() {
+                problems.push("Diff documents cannot be split into parts".to_string());
+            }
+        }
+
+        // Validate the files, which may each be listed with a single line range
+        for (index, file) in self.files.iter().enumerate() {
+            let earlier = self.files[..index].iter().find(|other| other.path() == file.path());
+            if earlier.is_some_and(|other| other.lines().is_some() || file.lines().is_some()) {
+                problems.push(format!("File listed more than once with a line range: {}", file.path().display()));
+            }
+        }
+
+        // Validate the content budget
+        if self.max_total_tokens == Some(0) || self.max_total_bytes == Some(0) {
+            problems.push("Total content budget cannot be zero".to_string());
+        }
+        for pattern in &self.priority {
+            if glob::Pattern::new(pattern).is_err() {
+                problems.push(format!("Invalid priority pattern: {}", pattern));
             }
         }
 
+        // Validate part limits
+        if self.max_tokens_per_part == Some(0) || self.max_bytes_per_part == Some(0) {
+            problems.push("Part size limits cannot be zero".to_string());
+        }
+        if (self.max_tokens_per_part.is_some() || self.max_bytes_per_part.is_some())
+            && self.output_format() != OutputFormat::Markdown
+        {
+            problems.push("Splitting output into parts is only supported for Markdown output".to_string());
+        }
+
         // Validate exclude patterns
-        for pattern in &self.exclude_patterns {
+        if self.exclude_patterns.iter().any(|pattern| pattern.trim().is_empty()) {
+            problems.push("Exclude pattern cannot be empty".to_string());
+        }
+
+        // Validate include patterns, both project-wide and per directory
+        let scoped_patterns = self.directories.iter().flat_map(|d| d.include_patterns());
+        for pattern in self.include_patterns.iter().chain(scoped_patterns) {
             if pattern.trim().is_empty() {
-                anyhow::bail!("Exclude pattern cannot be empty");
+                problems.push("Include pattern cannot be empty".to_string());
+            } else if glob::Pattern::new(pattern).is_err() {
+                problems.push(format!("Invalid include pattern: {}", pattern));
             }
         }
 
-        Ok(self.clone())
+        // Validate source encodings
+        if let Some(label) = &self.source_encoding {
+            if encoding_detector::encoding_for_label(label).is_none() {
+                problems.push(format!("Unknown source encoding: {}", label));
+            }
+        }
+        for encoding_override in &self.encoding_overrides {
+            if glob::Pattern::new(&encoding_override.pattern).is_err() {
+                problems.push(format!("Invalid encoding override pattern: {}", encoding_override.pattern));
+            }
+            if encoding_detector::encoding_for_label(&encoding_override.encoding).is_none() {
+                problems.push(format!("Unknown source encoding: {}", encoding_override.encoding));
+            }
+        }
+
+        problems
     }
 }
 
-impl Clone for Config {
-    fn clone(&self) -> Self {
-        Config {
-            project_name: self.project_name.clone(),
-            project_path: self.project_path.clone(),
-            output_file: self.output_file.clone(),
-            files: self.files.clone(),
-            directories: self.directories.clone(),
-            exclude_directories: self.exclude_directories.clone(),
-            exclude_patterns: self.exclude_patterns.clone(),
-            max_file_size: self.max_file_size,
-            markdown_lang: self.markdown_lang.clone(),
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_directory_entries() {
+        let config = test_config("directories: [src, {path: docs, include_patterns: [\"**/*.md\"]}, {path: tests}]");
+        let entries: Vec<(&Path, &[String])> = config
+            .directories
+            .iter()
+            .map(|entry| (entry.path(), entry.include_patterns()))
+            .collect();
+        assert_eq!(entries, vec![
+            (Path::new("src"), &[][..]),
+            (Path::new("docs"), &["**/*.md".to_string()][..]),
+            (Path::new("tests"), &[][..]),
+        ]);
+
+        let temp = tempfile::tempdir().unwrap();
+        let mut config = test_config("include_patterns: [\"*.rs\", \" \", \"[\"]\ndirectories: [{path: src, include_patterns: [\"src/[\"]}]");
+        config.project_path = temp.path().to_path_buf();
+        assert_eq!(config.problems(), vec![
+            "Include pattern cannot be empty".to_string(),
+            "Invalid include pattern: [".to_string(),
+            "Invalid include pattern: src/[".to_string(),
+        ]);
+    }
+
+    #[test]
+    fn test_parse_file_entries() {
+        let entry = |text: &str| FileEntry::try_from(text.to_string());
+        let parsed = |text: &str| {
+            let entry = entry(text).unwrap();
+            (entry.path().to_path_buf(), entry.lines().map(|lines| (lines.start, lines.end)))
+        };
+
+        assert_eq!(parsed("src/main.rs"), (PathBuf::from("src/main.rs"), None));
+        assert_eq!(parsed("src/main.rs#L10-80"), (PathBuf::from("src/main.rs"), Some((10, 80))));
+        assert_eq!(parsed("src/main.rs#L10-L80"), (PathBuf::from("src/main.rs"), Some((10, 80))));
+        assert_eq!(parsed("src/main.rs#L42"), (PathBuf::from("src/main.rs"), Some((42, 42))));
+        // A `#` not followed by a line range belongs to the file name
+        assert_eq!(parsed("notes#1.md"), (PathBuf::from("notes#1.md"), None));
+        assert_eq!(parsed("a#b#L3"), (PathBuf::from("a#b"), Some((3, 3))));
+        assert_eq!(parsed("src/main.rs#Lx"), (PathBuf::from("src/main.rs#Lx"), None));
+
+        for invalid in ["src/main.rs#L0", "src/main.rs#L80-10", "src/main.rs#L99999999999999999999999"] {
+            assert!(entry(invalid).is_err(), "{} should be rejected", invalid);
         }
+        assert!(serde_yaml::from_str::<Config>(
+            "project_name: Demo\nproject_path: /project\noutput_file: demo.md\nfiles: [\"a.rs#L0\"]\ndirectories: []\n"
+        )
+        .is_err());
     }
 }
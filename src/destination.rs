@@ -0,0 +1,154 @@
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+use anyhow::{Context, Result};
+use crate::project_options::ProjectOptions;
+
+/// Where generated documents are written.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Destination {
+    /// The output files, under the output directory.
+    Files,
+    /// Stdout, with an optional line written before each document.
+    Stdout { separator: Option<String> },
+}
+
+impl Destination {
+    /// Returns the destination selected by the `generate` arguments.
+    ///
+    /// # Arguments
+    ///
+    /// * `stdout` - Whether `--stdout` is given.
+    /// * `separator` - The `--separator` line, if any.
+    /// * `options` - Project options, where `-o -` also selects stdout.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Self>` - The destination, or an error if a separator is given without
+    ///   writing to stdout.
+    pub fn from_args(stdout: bool, separator: Option<String>, options: &ProjectOptions) -> Result<Self> {
+        if stdout || options.output.as_deref() == Some("-") {
+            Ok(Destination::Stdout { separator })
+        } else if separator.is_some() {
+            anyhow::bail!("--separator can only be used with --stdout");
+        } else {
+            Ok(Destination::Files)
+        }
+    }
+
+    /// Returns `true` if the given number of documents can be written; several documents
+    /// on stdout need a separator.
+    pub fn allows(&self, documents: usize) -> bool {
+        documents <= 1 || *self != Destination::Stdout { separator: None }
+    }
+
+    /// Describes where a document is written, for logging.
+    pub fn describe(&self, output_path: &Path) -> String {
+        match self {
+            Destination::Files => output_path.display().to_string(),
+            Destination::Stdout { .. } => "stdout".to_string(),
+        }
+    }
+
+    /// Writes a generated document to the destination.
+    ///
+    /// # Arguments
+    ///
+    /// * `project_name` - Name of the project, for the separator line.
+    /// * `output_path` - Path of the output file, also named by the separator line.
+    /// * `content` - The document.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<()>` - Success or error.
+    pub fn write(&self, project_name: &str, output_path: &Path, content: &str) -> Result<()> {
+        match self {
+            Destination::Files => write_output(output_path, content),
+            Destination::Stdout { separator } => {
+                let mut stdout = std::io::stdout().lock();
+                write_document(&mut stdout, separator.as_deref(), project_name, output_path, content)
+                    .and_then(|_| stdout.flush())
+                    .context("Failed to write to stdout")
+            }
+        }
+    }
+}
+
+/// Returns the separator line of a document, with `{project}` and `{file}` replaced by
+/// the project name and the output file name.
+pub fn separator_line(separator: &str, project_name: &str, output_path: &Path) -> String {
+    let file_name = output_path.file_name().unwrap_or_default().to_string_lossy();
+    separator.replace("{project}", project_name).replace("{file}", &file_name)
+}
+
+/// Writes a document preceded by its separator line, if any.
+///
+/// With a separator, the document is ended with a newline so that the next separator
+/// starts on its own line.
+fn write_document<W: Write>(
+    writer: &mut W,
+    separator: Option<&str>,
+    project_name: &str,
+    output_path: &Path,
+    content: &str,
+) -> std::io::Result<()> {
+    if let Some(separator) = separator {
+        writeln!(writer, "{}", separator_line(separator, project_name, output_path))?;
+    }
+    writer.write_all(content.as_bytes())?;
+    if separator.is_some() && !content.ends_with('\n') {
+        writeln!(writer)?;
+    }
+    Ok(())
+}
+
+/// Writes a generated document to a file with UTF-8 encoding.
+fn write_output(output_path: &Path, content: &str) -> Result<()> {
+    let mut file = fs::File::create(output_path)
+        .context(format!("Failed to create output file: {}", output_path.display()))?;
+    file.write_all(content.as_bytes())
+        .context(format!("Failed to write output file: {}", output_path.display()))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_args() {
+        let options = ProjectOptions::default();
+        let to_stdout = ProjectOptions { output: Some("-".to_string()), ..ProjectOptions::default() };
+        let separator = Some("== {file}".to_string());
+
+        assert_eq!(Destination::from_args(false, None, &options).unwrap(), Destination::Files);
+        assert_eq!(Destination::from_args(true, None, &options).unwrap(), Destination::Stdout { separator: None });
+        assert_eq!(Destination::from_args(false, None, &to_stdout).unwrap(), Destination::Stdout { separator: None });
+        assert_eq!(
+            Destination::from_args(false, separator.clone(), &to_stdout).unwrap(),
+            Destination::Stdout { separator: separator.clone() }
+        );
+        assert!(Destination::from_args(false, separator.clone(), &options).is_err());
+
+        // Several documents on stdout need a separator
+        assert!(Destination::Files.allows(3));
+        assert!(Destination::Stdout { separator: None }.allows(1));
+        assert!(!Destination::Stdout { separator: None }.allows(2));
+        assert!(Destination::Stdout { separator }.allows(2));
+    }
+
+    #[test]
+    fn test_write_document() {
+        let output_path = Path::new("output/demo.part1.md");
+        assert_eq!(separator_line("==> {file} ({project}) <==", "Demo", output_path), "==> demo.part1.md (Demo) <==");
+
+        let write = |separator: Option<&str>, content: &str| {
+            let mut buffer = Vec::new();
+            write_document(&mut buffer, separator, "Demo", output_path, content).unwrap();
+            String::from_utf8(buffer).unwrap()
+        };
+        assert_eq!(write(None, "# Demo"), "# Demo");
+        assert_eq!(write(Some("-- {file}"), "# Demo"), "-- demo.part1.md\n# Demo\n");
+        assert_eq!(write(Some("-- {file}"), "# Demo\n"), "-- demo.part1.md\n# Demo\n");
+    }
+}
@@ -0,0 +1,121 @@
+use std::path::PathBuf;
+use anyhow::Result;
+use crate::config::{Config, DiffRange};
+use crate::git_repository::{ChangeStatus, FileDiff};
+use crate::language::Languages;
+use crate::markdown_generator::{code_block, localized_text};
+use crate::{language, tree_generator};
+
+/// Generates a Markdown document describing the changes between two revisions.
+///
+/// The document contains the tree of changed files, each marked as added, modified or
+/// deleted, followed by the unified diff of every file. Diff hunks are fenced with the
+/// fence identifier of the file's language.
+///
+/// # Arguments
+///
+/// * `config` - The project configuration.
+/// * `range` - The compared revisions.
+/// * `files` - List of changed files with their paths and diffs.
+/// * `languages` - Language definitions.
+///
+/// # Returns
+///
+/// * `Result<String>` - The generated Markdown content or an error.
+pub fn generate_diff_markdown(
+    config: &Config,
+    range: &DiffRange,
+    files: Vec<(PathBuf, FileDiff)>,
+    languages: &Languages,
+) -> Result<String> {
+    let project_name = config.project_name.as_str();
+    let project_root = config.project_path.as_path();
+    let lang = config.markdown_lang.as_str();
+    let file_label = localized_text("file_label", lang);
+
+    // Sort files for consistent output
+    let mut sorted_files = files;
+    sorted_files.sort_by(|a, b| a.0.cmp(&b.0));
+
+    let mut markdown_content = format!(
+        "# {} for {}: `{}..{}`\n\n",
+        localized_text("changes", lang),
+        project_name,
+        range.from,
+        range.to
+    );
+    if sorted_files.is_empty() {
+        markdown_content.push_str(&format!("{}\n", localized_text("no_changes", lang)));
+        return Ok(markdown_content);
+    }
+
+    // Add the tree of changed files
+    markdown_content.push_str(&format!("## {}\n\n", localized_text("project_file_tree", lang)));
+    let tree = format!(
+        "{}\n{}",
+        project_name,
+        tree_generator::generate_diff_tree(project_name, &sorted_files, project_root)?
+    );
+    markdown_content.push_str(&code_block("", tree.strip_suffix('\n').unwrap_or(&tree)));
+
+    // Add the diff of each file
+    markdown_content.push_str(&format!("## {}\n\n", localized_text("changed_files", lang)));
+    for (file_path, diff) in &sorted_files {
+        let relative_path = file_path.strip_prefix(project_root).unwrap_or(file_path);
+        markdown_content.push_str(&format!(
+            "### {}: `{}`{}\n\n",
+            file_label,
+            relative_path.display(),
+            tree_generator::change_marker(diff.status)
+        ));
+        match &diff.hunks {
+            Some(hunks) if !hunks.is_empty() => {
+                let language = language::detect_language(file_path, None, languages).fence;
+                markdown_content.push_str(&code_block(&language, hunks.strip_suffix('\n').unwrap_or(hunks)));
+            }
+            // Without hunks, a modified file only changed its type or mode (e.g., executable)
+            Some(_) if diff.status == ChangeStatus::Modified => {
+                markdown_content.push_str(&format!("> {}\n\n", localized_text("file_type_changed", lang)));
+            }
+            // Added and deleted files without hunks are empty
+            Some(_) => markdown_content.push_str(&format!("> {}\n\n", localized_text("empty_file", lang))),
+            None => markdown_content.push_str(&format!("> {}\n\n", localized_text("binary_file", lang))),
+        }
+    }
+
+    Ok(markdown_content)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::test_config;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_generate_diff_markdown() {
+        let config = test_config("");
+        let range = DiffRange { from: "v1".to_string(), to: "HEAD".to_string() };
+        let files = vec![
+            (PathBuf::from("/project/src/main.rs"), FileDiff {
+                status: ChangeStatus::Modified,
+                hunks: Some("@@ -1 +1 @@\n-fn main() {}\n+fn main() { run() }\n".to_string()),
+            }),
+            (PathBuf::from("/project/logo.png"), FileDiff { status: ChangeStatus::Added, hunks: None }),
+            (PathBuf::from("/project/run.sh"), FileDiff { status: ChangeStatus::Modified, hunks: Some(String::new()) }),
+            (PathBuf::from("/project/empty.txt"), FileDiff { status: ChangeStatus::Added, hunks: Some(String::new()) }),
+        ];
+        let languages = Languages::from(HashMap::from([("rs".to_string(), "Rust".to_string())]));
+
+        let markdown = generate_diff_markdown(&config, &range, files, &languages).unwrap();
+        assert!(markdown.starts_with("# Changes for Demo: `v1..HEAD`\n\n## Project File Tree\n\n"));
+        assert!(markdown.contains("├── logo.png [added]\n├── run.sh [modified]\n└── src/\n    └── main.rs [modified]\n"));
+        assert!(markdown.contains("### File: `src/main.rs` [modified]\n\n```rust\n@@ -1 +1 @@\n-fn main() {}\n+fn main() { run() }\n```\n"));
+        assert!(markdown.contains("### File: `logo.png` [added]\n\n> Binary file\n"));
+        assert!(markdown.contains("### File: `run.sh` [modified]\n\n> File type or mode changed\n"));
+        assert!(markdown.contains("### File: `empty.txt` [added]\n\n> Empty file\n"));
+
+        let empty = generate_diff_markdown(&config, &range, Vec::new(), &languages).unwrap();
+        assert!(empty.ends_with("No files changed between the two revisions.\n"));
+    }
+}
@@ -0,0 +1,68 @@
+use chardetng::EncodingDetector;
+use encoding_rs::{Encoding, UTF_16BE, UTF_16LE};
+
+/// Looks up an encoding by its WHATWG label (e.g., "gbk", "shift_jis", "utf-16le").
+///
+/// # Arguments
+///
+/// * `label` - The encoding label, case-insensitive.
+///
+/// # Returns
+///
+/// * `Option<&'static Encoding>` - The encoding, or `None` if the label is unknown.
+pub fn encoding_for_label(label: &str) -> Option<&'static Encoding> {
+    Encoding::for_label(label.trim().as_bytes())
+}
+
+/// Returns `true` for UTF-16 encodings, whose text contains NUL bytes.
+pub fn is_utf16(encoding: &'static Encoding) -> bool {
+    encoding == UTF_16LE || encoding == UTF_16BE
+}
+
+/// Decodes bytes that start with a byte order mark.
+///
+/// # Arguments
+///
+/// * `bytes` - The raw file content.
+///
+/// # Returns
+///
+/// * `Option<String>` - The decoded text, or `None` if there is no BOM.
+pub fn decode_with_bom(bytes: &[u8]) -> Option<String> {
+    let (encoding, bom_length) = Encoding::for_bom(bytes)?;
+    Some(decode(&bytes[bom_length..], encoding))
+}
+
+/// Decodes bytes with the given encoding, replacing malformed sequences.
+///
+/// # Arguments
+///
+/// * `bytes` - The raw file content, without a BOM.
+/// * `encoding` - The encoding of the content.
+///
+/// # Returns
+///
+/// * `String` - The content transcoded to UTF-8.
+pub fn decode(bytes: &[u8], encoding: &'static Encoding) -> String {
+    let (text, had_errors) = encoding.decode_without_bom_handling(bytes);
+    if had_errors {
+        log::warn!("Malformed {} sequences were replaced while decoding", encoding.name());
+    }
+    text.into_owned()
+}
+
+/// Guesses the encoding of non-UTF-8 bytes and decodes them.
+///
+/// # Arguments
+///
+/// * `bytes` - The raw file content.
+///
+/// # Returns
+///
+/// * `(String, &'static Encoding)` - The content transcoded to UTF-8 and the detected encoding.
+pub fn detect_and_decode(bytes: &[u8]) -> (String, &'static Encoding) {
+    let mut detector = EncodingDetector::new();
+    detector.feed(bytes, true);
+    let encoding = detector.guess(None, false);
+    (decode(bytes, encoding), encoding)
+}
@@ -1,145 +1,550 @@
+use std::collections::HashSet;
 use std::fs;
 use std::io::Read;
 use std::path::{Path, PathBuf};
-use std::sync::{Arc, Mutex};
 use anyhow::{Context, Result};
+use encoding_rs::Encoding;
+use ignore::WalkBuilder;
 use rayon::prelude::*;
-use walkdir::WalkDir;
+use crate::binary_detector::{self, BinaryInfo};
+use crate::config::{Config, LineRange};
+use crate::{encoding_detector, git_repository};
+use crate::token_counter::TokenStats;
+
+/// Name of the tool-specific ignore file, read with `.gitignore` syntax.
+pub const MDIGNORE_FILENAME: &str = ".mdignore";
+
+/// Content of a file collected for the documentation.
+#[derive(Debug, Clone, PartialEq)]
+pub enum FileContent {
+    /// Text content.
+    Text(String),
+    /// Binary content, represented by its metadata only.
+    Binary(BinaryInfo),
+    /// Content left out to fit the global content budget.
+    Omitted {
+        /// Size of the file in bytes.
+        size: u64,
+    },
+}
+
+impl FileContent {
+    /// Returns the text content, or `None` for binary and omitted files.
+    pub fn as_text(&self) -> Option<&str> {
+        match self {
+            FileContent::Text(text) => Some(text),
+            _ => None,
+        }
+    }
+}
 
 /// Reads the content of a file.
 ///
+/// Files detected as binary are not decoded; their metadata is returned instead.
+/// Text is transcoded to UTF-8, using in order: the byte order mark, UTF-8 itself, the
+/// given encoding, and finally charset detection. A configured UTF-16 encoding is used
+/// before UTF-8, because UTF-16 text of ASCII characters is also valid UTF-8.
+///
 /// # Arguments
 ///
 /// * `file_path` - Path to the file to read.
+/// * `encoding` - Encoding configured for the file (optional).
 ///
 /// # Returns
 ///
-/// * `Result<String>` - The content of the file or an error.
-pub fn read_file_content(file_path: &Path) -> Result<String> {
+/// * `Result<FileContent>` - The content of the file or an error.
+pub fn read_file_content(file_path: &Path, encoding: Option<&'static Encoding>) -> Result<FileContent> {
     let mut file = fs::File::open(file_path)
         .context(format!("Failed to open file: {}", file_path.display()))?;
-    let mut content = String::new();
-    file.read_to_string(&mut content)
+    let mut bytes = Vec::new();
+    file.read_to_end(&mut bytes)
         .context(format!("Failed to read file: {}", file_path.display()))?;
-    Ok(content)
+
+    // Magic numbers are checked on the raw bytes, since a configured UTF-16 encoding
+    // decodes any file. UTF-16 text must be decoded before NUL bytes are sniffed, so text
+    // behind a BOM or in a configured UTF-16 encoding is sniffed once decoded
+    let decoded = if binary_detector::has_magic_number(&bytes) {
+        None
+    } else {
+        let utf16 = encoding.filter(|e| encoding_detector::is_utf16(e));
+        encoding_detector::decode_with_bom(&bytes)
+            .or_else(|| utf16.map(|encoding| encoding_detector::decode(&bytes, encoding)))
+    };
+    let is_binary = match &decoded {
+        Some(content) => binary_detector::is_binary_text(content),
+        None => binary_detector::is_binary(&bytes),
+    };
+    if is_binary {
+        log::debug!("Detected binary file: {}", file_path.display());
+        return Ok(FileContent::Binary(binary_detector::binary_info(file_path, &bytes)));
+    }
+    if let Some(content) = decoded {
+        return Ok(FileContent::Text(content));
+    }
+
+    match String::from_utf8(bytes) {
+        Ok(content) => Ok(FileContent::Text(content)),
+        Err(err) => match encoding {
+            Some(encoding) => Ok(FileContent::Text(encoding_detector::decode(err.as_bytes(), encoding))),
+            None => {
+                let (content, detected) = encoding_detector::detect_and_decode(err.as_bytes());
+                log::debug!("Detected {} encoding for file: {}", detected.name(), file_path.display());
+                Ok(FileContent::Text(content))
+            }
+        },
+    }
+}
+
+/// Resolves the encoding configured for a file.
+///
+/// The first matching entry of `encoding_overrides` wins, then `source_encoding` applies.
+///
+/// # Arguments
+///
+/// * `file_path` - Path to the file.
+/// * `config` - The project configuration.
+///
+/// # Returns
+///
+/// * `Option<&'static Encoding>` - The configured encoding, or `None` to detect it.
+fn configured_encoding(file_path: &Path, config: &Config) -> Option<&'static Encoding> {
+    let relative_path = file_path.strip_prefix(&config.project_path).unwrap_or(file_path);
+    let relative_path_str = relative_path.to_string_lossy();
+
+    config
+        .encoding_overrides
+        .iter()
+        .find(|o| {
+            glob::Pattern::new(&o.pattern)
+                .map(|compiled| compiled.matches(&relative_path_str))
+                .unwrap_or(false)
+        })
+        .map(|o| o.encoding.as_str())
+        .or(config.source_encoding.as_deref())
+        .and_then(encoding_detector::encoding_for_label)
 }
 
 /// Processes files and directories specified in the configuration.
 ///
 /// This function processes individual files and directories recursively, excluding
-/// directories and files specified in the configuration.
+/// directories and files specified in the configuration. Files listed explicitly in
+/// `files` are always read, even if an ignore file, `include_patterns` or `git_filter`
+/// would otherwise skip them.
 ///
 /// # Arguments
 ///
-/// * `project_path` - Path to the project root directory.
-/// * `files` - List of specific files to process.
-/// * `directories` - List of directories to process recursively.
-/// * `exclude_directories` - List of directories to exclude from processing.
-/// * `exclude_patterns` - List of glob patterns to exclude files.
-/// * `max_file_size` - Maximum file size in bytes (optional).
+/// * `config` - The project configuration.
 ///
 /// # Returns
 ///
-/// * `Result<Vec<(PathBuf, String)>>` - A list of file paths and their contents.
-pub fn process_files(
-    project_path: &PathBuf,
-    files: &[PathBuf],
-    directories: &[PathBuf],
-    exclude_directories: &[String],
-    exclude_patterns: &[String],
-    max_file_size: Option<u64>,
-) -> Result<Vec<(PathBuf, String)>> {
+/// * `Result<Vec<(PathBuf, FileContent)>>` - A list of file paths and their contents.
+pub fn process_files(config: &Config) -> Result<Vec<(PathBuf, FileContent)>> {
     let mut file_contents = Vec::new();
 
     // Process individual files
-    for file in files {
-        let full_path = project_path.join(file);
-        if full_path.exists() && full_path.is_file() {
-            if should_include_file(&full_path, exclude_patterns, max_file_size, project_path)? {
-                let content = read_file_content(&full_path)?;
-                file_contents.push((full_path, content));
+    let listed_patterns = FilePatterns::new(&[], &config.project_path, &config.exclude_patterns);
+    for file in &config.files {
+        let full_path = config.project_path.join(file.path());
+        if is_listed_file_included(&full_path, &listed_patterns, config)? {
+            let mut content = read_file_content(&full_path, configured_encoding(&full_path, config))?;
+            if let (Some(lines), FileContent::Text(text)) = (file.lines(), &content) {
+                content = FileContent::Text(excerpt(text, lines)
+                    .context(format!("Failed to read excerpt of file: {}", full_path.display()))?);
             }
+            file_contents.push((full_path, content));
         }
     }
 
-    // Process files within directories recursively
-    for dir in directories {
-        let full_dir = project_path.join(dir);
-        if full_dir.exists() && full_dir.is_dir() {
-            process_directory_parallel(&full_dir, &mut file_contents, exclude_directories, exclude_patterns, max_file_size, project_path)?;
+    // Process files within directories recursively, in parallel
+    let mut directory_contents: Vec<(PathBuf, FileContent)> = select_directory_files(config)?
+        .into_par_iter()
+        .filter_map(|path| match read_file_content(&path, configured_encoding(&path, config)) {
+            Ok(content) => Some((path, content)),
+            Err(e) => {
+                log::warn!("Failed to read file {}: {}", path.display(), e);
+                None
+            }
+        })
+        .collect();
+    file_contents.append(&mut directory_contents);
+
+    // A file listed in `files` may also be found in `directories`; keep it only once,
+    // preferring the entry of `files`, which may be an excerpt
+    file_contents.sort_by(|a, b| a.0.cmp(&b.0));
+    file_contents.dedup_by(|a, b| a.0 == b.0);
+
+    Ok(file_contents)
+}
+
+/// Selects the files documented by the configuration, without reading them.
+///
+/// Applies the same rules as [`process_files`], including ignore files, `git_filter`
+/// and `max_file_size`, but does not detect binary files or apply line ranges.
+///
+/// # Arguments
+///
+/// * `config` - The project configuration.
+///
+/// # Returns
+///
+/// * `Result<Vec<PathBuf>>` - The sorted paths of the selected files.
+pub fn select_files(config: &Config) -> Result<Vec<PathBuf>> {
+    let mut file_paths = Vec::new();
+    let listed_patterns = FilePatterns::new(&[], &config.project_path, &config.exclude_patterns);
+    for file in &config.files {
+        let full_path = config.project_path.join(file.path());
+        if is_listed_file_included(&full_path, &listed_patterns, config)? {
+            file_paths.push(full_path);
         }
     }
+    file_paths.extend(select_directory_files(config)?);
 
-    Ok(file_contents)
+    file_paths.sort();
+    file_paths.dedup();
+    Ok(file_paths)
 }
 
-/// Processes files within a directory in parallel using rayon.
+/// Returns `true` if a file listed in `files` exists and passes the exclude patterns
+/// and size limit.
+fn is_listed_file_included(full_path: &Path, patterns: &FilePatterns, config: &Config) -> Result<bool> {
+    Ok(full_path.is_file() && should_include_file(full_path, patterns, config.max_file_size, &config.project_path)?)
+}
+
+/// Selects the files found in the configured `directories`, without reading them.
 ///
 /// # Arguments
 ///
-/// * `dir` - Path to the directory to process.
-/// * `file_contents` - Vector to store file paths and their contents.
-/// * `exclude_directories` - List of directories to exclude from processing.
-/// * `exclude_patterns` - List of glob patterns to exclude files.
-/// * `max_file_size` - Maximum file size in bytes (optional).
-/// * `project_root` - Path to the project root directory.
+/// * `config` - The project configuration.
 ///
 /// # Returns
 ///
-/// * `Result<()>` - Success or error.
-fn process_directory_parallel(
+/// * `Result<Vec<PathBuf>>` - The paths of the selected files.
+fn select_directory_files(config: &Config) -> Result<Vec<PathBuf>> {
+    let project_path = &config.project_path;
+
+    // Select files from the git repository, if requested
+    let git_selection = config
+        .git_filter
+        .as_ref()
+        .map(|filter| git_repository::select_files(project_path, filter))
+        .transpose()?;
+
+    let mut file_paths = Vec::new();
+    for dir in &config.directories {
+        let full_dir = project_path.join(dir.path());
+        if full_dir.is_dir() {
+            // Patterns scoped to the directory replace the project-wide ones
+            let (include_patterns, include_base) = if dir.include_patterns().is_empty() {
+                (config.include_patterns.as_slice(), project_path.as_path())
+            } else {
+                (dir.include_patterns(), full_dir.as_path())
+            };
+            let patterns = FilePatterns::new(include_patterns, include_base, &config.exclude_patterns);
+            file_paths.extend(select_directory_parallel(&full_dir, &patterns, git_selection.as_ref(), config));
+        }
+    }
+    Ok(file_paths)
+}
+
+/// Returns the lines of a text within a line range.
+///
+/// A range ending past the end of the text is cut at the last line.
+///
+/// # Arguments
+///
+/// * `text` - The text to take lines from.
+/// * `lines` - The lines to take.
+///
+/// # Returns
+///
+/// * `Result<String>` - The lines, with their line endings, or an error if the range
+///   starts past the end of the text.
+pub fn excerpt(text: &str, lines: LineRange) -> Result<String> {
+    let line_count = text.split_inclusive('\n').count();
+    if lines.start > line_count {
+        anyhow::bail!("Line range L{}-{} starts past the end of the file ({} lines)", lines.start, lines.end, line_count);
+    }
+    Ok(text
+        .split_inclusive('\n')
+        .skip(lines.start - 1)
+        .take(lines.end - lines.start + 1)
+        .collect())
+}
+
+/// Returns `true` if a file belongs to the files documented by the configuration.
+///
+/// Applies the same rules as [`process_files`] to a path that may not exist on disk
+/// (e.g., a file deleted in a later revision): it must be listed in `files`, or lie
+/// under a `directories` entry outside `exclude_directories` and pass the include and
+/// exclude patterns. Ignore files, `git_filter` and `max_file_size` are not applied.
+///
+/// # Arguments
+///
+/// * `file_path` - Path to the file, under the project root.
+/// * `config` - The project configuration.
+///
+/// # Returns
+///
+/// * `bool` - `true` if the file is in scope, `false` otherwise.
+pub fn is_in_scope(file_path: &Path, config: &Config) -> bool {
+    let project_path = &config.project_path;
+
+    if config.files.iter().any(|file| project_path.join(file.path()) == file_path) {
+        let patterns = FilePatterns::new(&[], project_path, &config.exclude_patterns);
+        return should_include_file(file_path, &patterns, None, project_path).unwrap_or(false);
+    }
+
+    config.directories.iter().any(|dir| {
+        let full_dir = project_path.join(dir.path());
+        let Ok(relative_path) = file_path.strip_prefix(&full_dir) else { return false };
+        let excluded = relative_path
+            .parent()
+            .into_iter()
+            .flat_map(Path::ancestors)
+            .filter(|ancestor| !ancestor.as_os_str().is_empty())
+            .any(|ancestor| should_exclude_directory(&full_dir.join(ancestor), &config.exclude_directories));
+        if excluded {
+            return false;
+        }
+
+        // Patterns scoped to the directory replace the project-wide ones
+        let (include_patterns, include_base) = if dir.include_patterns().is_empty() {
+            (config.include_patterns.as_slice(), project_path.as_path())
+        } else {
+            (dir.include_patterns(), full_dir.as_path())
+        };
+        let patterns = FilePatterns::new(include_patterns, include_base, &config.exclude_patterns);
+        should_include_file(file_path, &patterns, None, project_path).unwrap_or(false)
+    })
+}
+
+/// Keeps the most important files within the global content budget.
+///
+/// Files are considered in `priority` order: files matching the first pattern come
+/// first, files matching no pattern last, and ties are broken by path. Each file is
+/// kept if it still fits in `max_total_tokens` / `max_total_bytes`; otherwise its
+/// content is replaced by [`FileContent::Omitted`]. Binary files cost nothing.
+///
+/// # Arguments
+///
+/// * `files` - List of files with their paths and contents.
+/// * `config` - The project configuration.
+/// * `token_stats` - Token counts of the files.
+///
+/// # Returns
+///
+/// * `Vec<(PathBuf, FileContent)>` - The files, in the same order, with omitted contents.
+pub fn apply_budget(
+    files: Vec<(PathBuf, FileContent)>,
+    config: &Config,
+    token_stats: &TokenStats,
+) -> Vec<(PathBuf, FileContent)> {
+    if config.max_total_tokens.is_none() && config.max_total_bytes.is_none() {
+        return files;
+    }
+
+    let tokens: std::collections::HashMap<&Path, usize> = token_stats
+        .files
+        .iter()
+        .map(|(path, tokens)| (path.as_path(), *tokens))
+        .collect();
+    let priorities: Vec<glob::Pattern> = config
+        .priority
+        .iter()
+        .filter_map(|pattern| glob::Pattern::new(pattern).ok())
+        .collect();
+    let priority_of = |file_path: &Path| {
+        let relative_path = file_path.strip_prefix(&config.project_path).unwrap_or(file_path);
+        let relative_path_str = relative_path.to_string_lossy();
+        priorities
+            .iter()
+            .position(|pattern| pattern.matches(&relative_path_str))
+            .unwrap_or(priorities.len())
+    };
+
+    let mut order: Vec<usize> = (0..files.len()).collect();
+    order.sort_by_key(|&i| (priority_of(&files[i].0), files[i].0.clone()));
+
+    // Fill the budget in priority order, skipping files that no longer fit
+    let mut omitted = vec![false; files.len()];
+    let mut used_tokens = 0;
+    let mut used_bytes = 0;
+    for i in order {
+        let (file_path, content) = &files[i];
+        let (file_tokens, file_bytes) = match content {
+            FileContent::Text(text) => (tokens.get(file_path.as_path()).copied().unwrap_or(0), text.len() as u64),
+            _ => (0, 0),
+        };
+        let fits = config.max_total_tokens.is_none_or(|max| used_tokens + file_tokens <= max)
+            && config.max_total_bytes.is_none_or(|max| used_bytes + file_bytes <= max);
+        if fits {
+            used_tokens += file_tokens;
+            used_bytes += file_bytes;
+        } else {
+            log::info!("Omitting file to fit the content budget: {}", file_path.display());
+            omitted[i] = true;
+        }
+    }
+
+    files
+        .into_iter()
+        .zip(omitted)
+        .map(|((file_path, content), omitted)| match (omitted, content) {
+            (true, FileContent::Text(text)) => (file_path, FileContent::Omitted { size: text.len() as u64 }),
+            (_, content) => (file_path, content),
+        })
+        .collect()
+}
+
+/// Selects the files within a directory, checking them in parallel using rayon.
+///
+/// # Arguments
+///
+/// * `dir` - Path to the directory to walk.
+/// * `patterns` - Include and exclude patterns of the directory.
+/// * `git_selection` - Files selected by `git_filter`, if any.
+/// * `config` - The project configuration.
+///
+/// # Returns
+///
+/// * `Vec<PathBuf>` - The paths of the selected files.
+fn select_directory_parallel(
     dir: &Path,
-    file_contents: &mut Vec<(PathBuf, String)>,
-    exclude_directories: &[String],
-    exclude_patterns: &[String],
-    max_file_size: Option<u64>,
-    project_root: &Path,
-) -> Result<()> {
+    patterns: &FilePatterns,
+    git_selection: Option<&HashSet<PathBuf>>,
+    config: &Config,
+) -> Vec<PathBuf> {
+    let max_file_size = config.max_file_size;
+    let project_root = config.project_path.as_path();
+
     // Collect all file paths first
-    let file_paths: Vec<PathBuf> = WalkDir::new(dir)
-        .into_iter()
-        .filter_entry(|e| !should_exclude_directory(e.path(), exclude_directories))
-        .filter_map(|e| e.ok())
-        .filter(|e| e.file_type().is_file())
-        .map(|e| e.path().to_path_buf())
+    let file_paths: Vec<PathBuf> = walk_directory(dir, &config.exclude_directories, config.respect_ignore_files)
+        .filter(|e| e.file_type().is_some_and(|t| t.is_file()))
+        .map(|e| e.into_path())
+        .filter(|path| git_selection.is_none_or(|selection| git_repository::is_selected(path, selection)))
         .collect();
 
-    // Process files in parallel
-    let file_contents_mutex = Arc::new(Mutex::new(Vec::new()));
-    
-    file_paths.par_iter()
-        .filter_map(|path| {
-            if should_include_file(path, exclude_patterns, max_file_size, project_root).unwrap_or(false) {
-                match read_file_content(path) {
-                    Ok(content) => Some((path.clone(), content)),
-                    Err(e) => {
-                        log::warn!("Failed to read file {}: {}", path.display(), e);
-                        None
-                    }
-                }
-            } else {
-                None
-            }
+    // Check files in parallel
+    file_paths.into_par_iter()
+        .filter(|path| {
+            should_include_file(path, patterns, max_file_size, project_root).unwrap_or(false)
         })
-        .for_each(|(path, content)| {
-            file_contents_mutex.lock().unwrap().push((path, content));
+        .collect()
+}
+
+/// Builds a recursive directory walker.
+///
+/// When `respect_ignore_files` is set, the walker honors the `.gitignore` hierarchy
+/// (including parent directories, `.git/info/exclude` and the global git excludes
+/// file), `.ignore` files and the tool-specific `.mdignore` file, and skips `.git`
+/// directories. Hidden files are included either way.
+///
+/// # Arguments
+///
+/// * `dir` - Path to the directory to walk.
+/// * `exclude_directories` - List of directories to exclude from the walk.
+/// * `respect_ignore_files` - Whether ignore files should be honored.
+///
+/// # Returns
+///
+/// * `impl Iterator<Item = ignore::DirEntry>` - The entries found under `dir`.
+fn walk_directory(
+    dir: &Path,
+    exclude_directories: &[String],
+    respect_ignore_files: bool,
+) -> impl Iterator<Item = ignore::DirEntry> {
+    let exclude_directories = exclude_directories.to_vec();
+    let mut builder = WalkBuilder::new(dir);
+    builder
+        .standard_filters(respect_ignore_files)
+        .hidden(false)
+        .require_git(false)
+        .filter_entry(move |e| {
+            if respect_ignore_files
+                && e.file_name() == ".git"
+                && e.file_type().is_some_and(|t| t.is_dir())
+            {
+                return false;
+            }
+            !should_exclude_directory(e.path(), &exclude_directories)
         });
+    if respect_ignore_files {
+        builder.add_custom_ignore_filename(MDIGNORE_FILENAME);
+    }
 
-    let mut collected = Arc::try_unwrap(file_contents_mutex)
-        .unwrap()
-        .into_inner()
-        .unwrap();
-    
-    file_contents.append(&mut collected);
-    Ok(())
+    builder.build().filter_map(|e| match e {
+        Ok(entry) => Some(entry),
+        Err(err) => {
+            log::warn!("Failed to walk directory entry: {}", err);
+            None
+        }
+    })
 }
 
-/// Determines whether a file should be included based on exclude patterns and size limit.
+/// Options used to match include patterns: as in `.gitignore`, `*` does not match `/`,
+/// so `*.toml` only matches files directly in the base directory and `**/*.toml` matches
+/// them at any depth.
+const INCLUDE_MATCH_OPTIONS: glob::MatchOptions = glob::MatchOptions {
+    case_sensitive: true,
+    require_literal_separator: true,
+    require_literal_leading_dot: false,
+};
+
+/// A pattern of `exclude_patterns`.
+enum ExcludePattern {
+    /// A glob pattern, matched against the path relative to the project root; `*` also
+    /// matches `/`, so `*.log` excludes log files at any depth.
+    Glob(glob::Pattern),
+    /// A pattern without wildcards, matching a path or the paths under it.
+    Prefix(String),
+}
+
+/// Include and exclude patterns, compiled once before files are checked.
+struct FilePatterns<'a> {
+    /// Patterns a file must match, if any.
+    include: Vec<glob::Pattern>,
+    /// Directory the include patterns are relative to.
+    include_base: &'a Path,
+    /// Patterns excluding files.
+    exclude: Vec<ExcludePattern>,
+}
+
+impl<'a> FilePatterns<'a> {
+    /// Compiles include and exclude patterns; invalid glob patterns never match.
+    ///
+    /// # Arguments
+    ///
+    /// * `include_patterns` - List of glob patterns a file must match (empty means all files).
+    /// * `include_base` - Directory the include patterns are relative to.
+    /// * `exclude_patterns` - List of glob patterns to exclude files.
+    fn new(include_patterns: &[String], include_base: &'a Path, exclude_patterns: &[String]) -> Self {
+        let include = include_patterns
+            .iter()
+            .filter_map(|pattern| glob::Pattern::new(pattern).ok())
+            .collect();
+        let exclude = exclude_patterns
+            .iter()
+            .filter_map(|pattern| {
+                if pattern.contains('*') || pattern.contains('?') {
+                    glob::Pattern::new(pattern).ok().map(ExcludePattern::Glob)
+                } else {
+                    Some(ExcludePattern::Prefix(pattern.clone()))
+                }
+            })
+            .collect();
+        FilePatterns { include, include_base, exclude }
+    }
+}
+
+/// Determines whether a file should be included based on include/exclude patterns and size limit.
+///
+/// Include patterns are checked first: when any are given, the file must match at least
+/// one of them. Exclude patterns are then applied to the remaining files.
 ///
 /// # Arguments
 ///
 /// * `file_path` - Path to the file to check.
-/// * `exclude_patterns` - List of glob patterns to exclude files.
+/// * `patterns` - Include and exclude patterns.
 /// * `max_file_size` - Maximum file size in bytes (optional).
 /// * `project_root` - Path to the project root directory.
 ///
@@ -148,7 +553,7 @@ fn process_directory_parallel(
 /// * `Result<bool>` - `true` if the file should be included, `false` otherwise.
 fn should_include_file(
     file_path: &Path,
-    exclude_patterns: &[String],
+    patterns: &FilePatterns,
     max_file_size: Option<u64>,
     project_root: &Path,
 ) -> Result<bool> {
@@ -163,25 +568,33 @@ fn should_include_file(
         }
     }
 
+    // Check include patterns
+    if !patterns.include.is_empty() {
+        let include_path = file_path.strip_prefix(patterns.include_base).unwrap_or(file_path);
+        let included = patterns
+            .include
+            .iter()
+            .any(|pattern| pattern.matches_path_with(include_path, INCLUDE_MATCH_OPTIONS));
+        if !included {
+            log::debug!("Skipping file not matching include patterns: {}", file_path.display());
+            return Ok(false);
+        }
+    }
+
     // Check exclude patterns
     let relative_path = file_path.strip_prefix(project_root).unwrap_or(file_path);
     let relative_path_str = relative_path.to_string_lossy();
     
-    for pattern in exclude_patterns {
-        if pattern.contains('*') || pattern.contains('?') {
+    for pattern in &patterns.exclude {
+        let (excluded, pattern) = match pattern {
             // Use glob pattern matching
-            if let Ok(compiler) = glob::Pattern::new(pattern) {
-                if compiler.matches(&relative_path_str) {
-                    log::debug!("Skipping file due to pattern '{}': {}", pattern, file_path.display());
-                    return Ok(false);
-                }
-            }
-        } else {
+            ExcludePattern::Glob(glob) => (glob.matches(&relative_path_str), glob.as_str()),
             // Exact match or directory match
-            if *relative_path_str == *pattern || relative_path_str.starts_with(pattern.as_str()) {
-                log::debug!("Skipping file due to pattern '{}': {}", pattern, file_path.display());
-                return Ok(false);
-            }
+            ExcludePattern::Prefix(prefix) => (relative_path_str.starts_with(prefix.as_str()), prefix.as_str()),
+        };
+        if excluded {
+            log::debug!("Skipping file due to pattern '{}': {}", pattern, file_path.display());
+            return Ok(false);
         }
     }
 
@@ -202,8 +615,7 @@ fn should_exclude_directory(dir: &Path, exclude_directories: &[String]) -> bool
     for pattern in exclude_directories {
         if pattern == "**" {
             return true;
-        } else if pattern.starts_with("**/") {
-            let dir_name_to_exclude = &pattern[3..];
+        } else if let Some(dir_name_to_exclude) = pattern.strip_prefix("**/") {
             let current_dir_name = dir.file_name()
                 .and_then(|os_str| os_str.to_str())
                 .unwrap_or("");
@@ -241,6 +653,7 @@ fn should_exclude_directory(dir: &Path, exclude_directories: &[String]) -> bool
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::config::test_config;
     use std::fs::File;
     use std::io::Write;
     use tempfile::TempDir;
@@ -252,8 +665,146 @@ mod tests {
         let mut file = File::create(&file_path).unwrap();
         writeln!(file, "Hello, World!").unwrap();
 
-        let content = read_file_content(&file_path).unwrap();
-        assert_eq!(content, "Hello, World!\n");
+        let content = read_file_content(&file_path, None).unwrap();
+        assert_eq!(content, FileContent::Text("Hello, World!\n".to_string()));
+    }
+
+    #[test]
+    fn test_read_binary_file_content() {
+        let temp_dir = TempDir::new().unwrap();
+        let png_path = temp_dir.path().join("logo.png");
+        fs::write(&png_path, b"\x89PNG\r\n\x1a\n\x00\x00\x00\rIHDR").unwrap();
+        let jar_path = temp_dir.path().join("lib.jar");
+        fs::write(&jar_path, b"PK\x03\x04META-INF/").unwrap();
+
+        match read_file_content(&png_path, None).unwrap() {
+            FileContent::Binary(info) => {
+                assert_eq!(info.size, 16);
+                assert_eq!(info.mime_type, "image/png");
+                assert_eq!(info.sha256.len(), 64);
+            }
+            other => panic!("Expected binary content, got {:?}", other),
+        }
+        match read_file_content(&jar_path, None).unwrap() {
+            FileContent::Binary(info) => assert_eq!(info.mime_type, "application/java-archive"),
+            other => panic!("Expected binary content, got {:?}", other),
+        }
+
+        // Binary data starting with the bytes of a UTF-16 byte order mark
+        for (name, bom) in [("data_le.bin", [0xFF, 0xFE]), ("data_be.bin", [0xFE, 0xFF])] {
+            let path = temp_dir.path().join(name);
+            let mut bytes = bom.to_vec();
+            bytes.extend_from_slice(&[0x00, 0x00, 0x01, 0x02, 0x00, 0x00, 0x03, 0x04]);
+            fs::write(&path, bytes).unwrap();
+            assert!(matches!(read_file_content(&path, None).unwrap(), FileContent::Binary(_)));
+        }
+
+        // Magic numbers are recognized even when a UTF-16 encoding is configured
+        let png_path = temp_dir.path().join("icon.png");
+        fs::write(&png_path, b"\x89PNG\r\n\x1a\nIHDR").unwrap();
+        match read_file_content(&png_path, encoding_detector::encoding_for_label("UTF-16LE")).unwrap() {
+            FileContent::Binary(info) => assert_eq!(info.mime_type, "image/png"),
+            other => panic!("Expected binary content, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_read_file_content_with_encodings() {
+        let temp_dir = TempDir::new().unwrap();
+        let text = "你好，世界";
+        let read = |name: &str, bytes: Vec<u8>, label: Option<&str>| {
+            let path = temp_dir.path().join(name);
+            fs::write(&path, bytes).unwrap();
+            read_file_content(&path, label.and_then(encoding_detector::encoding_for_label)).unwrap()
+        };
+        let expected = FileContent::Text(text.to_string());
+
+        // UTF-8 with BOM
+        let mut utf8_bom = vec![0xEF, 0xBB, 0xBF];
+        utf8_bom.extend_from_slice(text.as_bytes());
+        assert_eq!(read("bom.txt", utf8_bom, None), expected);
+
+        // UTF-16LE with BOM, and without BOM when configured
+        let utf16: Vec<u8> = text.encode_utf16().flat_map(|u| u.to_le_bytes()).collect();
+        let mut utf16_bom = vec![0xFF, 0xFE];
+        utf16_bom.extend_from_slice(&utf16);
+        assert_eq!(read("utf16_bom.txt", utf16_bom, None), expected);
+        assert_eq!(read("utf16.txt", utf16, Some("utf-16le")), expected);
+
+        // GBK, configured and detected
+        let (gbk, _, _) = encoding_rs::GBK.encode(text);
+        assert_eq!(read("gbk.txt", gbk.to_vec(), Some("gbk")), expected);
+        // Valid UTF-8 is kept as is even when another encoding is configured
+        assert_eq!(read("utf8_with_gbk.txt", text.as_bytes().to_vec(), Some("gbk")), expected);
+        let (gbk, _, _) = encoding_rs::GBK.encode("这是一个用于测试编码检测的中文句子，包含足够多的汉字。");
+        assert_eq!(
+            read("gbk_detected.txt", gbk.to_vec(), None),
+            FileContent::Text("这是一个用于测试编码检测的中文句子，包含足够多的汉字。".to_string())
+        );
+    }
+
+    #[test]
+    fn test_apply_budget() {
+        let config = test_config("max_total_bytes: 10\npriority: [\"src/**\", \"*.toml\"]");
+        let text = |n: usize| FileContent::Text("x".repeat(n));
+        let files = vec![
+            (PathBuf::from("/project/Cargo.toml"), text(3)),
+            (PathBuf::from("/project/README.md"), text(2)),
+            (PathBuf::from("/project/src/lib.rs"), text(4)),
+            (PathBuf::from("/project/src/main.rs"), text(5)),
+        ];
+        let token_stats = TokenStats {
+            encoding: crate::config::TokenEncoding::Estimate,
+            files: Vec::new(),
+            total: 0,
+        };
+
+        let kept = apply_budget(files, &config, &token_stats);
+        assert_eq!(kept[0].1, FileContent::Omitted { size: 3 });
+        assert_eq!(kept[1].1, FileContent::Omitted { size: 2 });
+        assert_eq!(kept[2].1, text(4));
+        assert_eq!(kept[3].1, text(5));
+    }
+
+    #[test]
+    fn test_is_in_scope() {
+        let config = test_config(
+            "files: [Cargo.toml]\ndirectories: [src, {path: docs, include_patterns: [\"*.md\"]}]\n\
+             exclude_directories: [generated]\nexclude_patterns: [\"*.log\"]",
+        );
+        let in_scope = |path: &str| is_in_scope(&Path::new("/project").join(path), &config);
+
+        assert!(in_scope("Cargo.toml"));
+        assert!(in_scope("src/main.rs"));
+        assert!(in_scope("docs/guide.md"));
+        assert!(!in_scope("README.md"));
+        assert!(!in_scope("src/generated/api.rs"));
+        assert!(!in_scope("src/debug.log"));
+        assert!(!in_scope("docs/logo.png"));
+    }
+
+    #[test]
+    fn test_process_files_with_line_range() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        fs::create_dir(root.join("src")).unwrap();
+        fs::write(root.join("src/main.rs"), "one\ntwo\nthree\nfour\n").unwrap();
+        fs::write(root.join("src/lib#1.rs"), "lib\n").unwrap();
+        let config = test_config(&format!(
+            "project_path: {:?}\nfiles: [\"src/main.rs#L2-3\", \"src/lib#1.rs\"]\ndirectories: [src]",
+            root
+        ));
+
+        let files = process_files(&config).unwrap();
+        assert_eq!(files, vec![
+            (root.join("src/lib#1.rs"), FileContent::Text("lib\n".to_string())),
+            (root.join("src/main.rs"), FileContent::Text("two\nthree\n".to_string())),
+        ]);
+        assert_eq!(config.line_range(&root.join("src/main.rs")), Some(LineRange { start: 2, end: 3 }));
+
+        assert_eq!(excerpt("a\nb", LineRange { start: 2, end: 9 }).unwrap(), "b");
+        assert!(excerpt("a\nb", LineRange { start: 3, end: 3 }).is_err());
+        assert!(serde_yaml::from_str::<crate::config::FileEntry>("\"a.rs#L9-2\"").is_err());
     }
 
     #[test]
@@ -271,16 +822,90 @@ mod tests {
         let mut file = File::create(&file_path).unwrap();
         writeln!(file, "Hello").unwrap();
 
+        let root = temp_dir.path();
+        let excludes = |patterns: &[&str]| {
+            let patterns: Vec<String> = patterns.iter().map(|p| p.to_string()).collect();
+            FilePatterns::new(&[], root, &patterns)
+        };
+
         // No exclusions
-        assert!(should_include_file(&file_path, &[], None, temp_dir.path()).unwrap());
+        assert!(should_include_file(&file_path, &excludes(&[]), None, root).unwrap());
 
         // With exclude pattern
-        assert!(!should_include_file(&file_path, &["*.txt".to_string()], None, temp_dir.path()).unwrap());
+        assert!(!should_include_file(&file_path, &excludes(&["*.txt"]), None, root).unwrap());
+        assert!(!should_include_file(&file_path, &excludes(&["test"]), None, root).unwrap());
 
         // With size limit (small file)
-        assert!(should_include_file(&file_path, &[], Some(1024), temp_dir.path()).unwrap());
+        assert!(should_include_file(&file_path, &excludes(&[]), Some(1024), root).unwrap());
 
         // With size limit (file too large)
-        assert!(!should_include_file(&file_path, &[], Some(1), temp_dir.path()).unwrap());
+        assert!(!should_include_file(&file_path, &excludes(&[]), Some(1), root).unwrap());
+    }
+
+    #[test]
+    fn test_should_include_file_with_include_patterns() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        fs::create_dir_all(root.join("src/nested")).unwrap();
+        let rust_file = root.join("src/nested/lib.rs");
+        let text_file = root.join("src/notes.txt");
+        let top_toml = root.join("Cargo.toml");
+        let nested_toml = root.join("src/nested/config.toml");
+        for file in [&rust_file, &text_file, &top_toml, &nested_toml] {
+            fs::write(file, "x").unwrap();
+        }
+        fn patterns<'a>(includes: &[&str], base: &'a Path, excludes: &[&str]) -> FilePatterns<'a> {
+            let includes: Vec<String> = includes.iter().map(|p| p.to_string()).collect();
+            let excludes: Vec<String> = excludes.iter().map(|p| p.to_string()).collect();
+            FilePatterns::new(&includes, base, &excludes)
+        }
+        let included = |file: &Path, patterns: &FilePatterns| should_include_file(file, patterns, None, root).unwrap();
+
+        let includes = patterns(&["src/**/*.rs", "*.toml"], root, &[]);
+        assert!(included(&rust_file, &includes));
+        assert!(!included(&text_file, &includes));
+
+        // `*` does not match `/`, while `**/` matches any depth, including none
+        assert!(included(&top_toml, &includes));
+        assert!(!included(&nested_toml, &includes));
+        let any_depth = patterns(&["**/*.toml"], root, &[]);
+        assert!(included(&top_toml, &any_depth));
+        assert!(included(&nested_toml, &any_depth));
+
+        // Includes are evaluated before excludes
+        assert!(!included(&rust_file, &patterns(&["src/**/*.rs"], root, &["**/nested/**"])));
+        // Exclude patterns keep matching `/` with `*`
+        assert!(!included(&nested_toml, &patterns(&[], root, &["*.toml"])));
+
+        // Scoped patterns are relative to their own directory
+        assert!(included(&rust_file, &patterns(&["nested/*.rs"], &root.join("src"), &[])));
+        assert!(!included(&rust_file, &patterns(&["*.rs"], &root.join("src"), &[])));
+    }
+
+    #[test]
+    fn test_walk_directory_respects_ignore_files() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        fs::create_dir_all(root.join("build")).unwrap();
+        fs::create_dir_all(root.join("src/generated")).unwrap();
+        fs::write(root.join(".gitignore"), "*.log\nbuild/\n").unwrap();
+        fs::write(root.join(".mdignore"), "notes.txt\n").unwrap();
+        fs::write(root.join("src/generated/.gitignore"), "*\n!keep.rs\n").unwrap();
+        for file in ["main.rs", "debug.log", "notes.txt", "build/out.bin", "src/lib.rs",
+                     "src/generated/keep.rs", "src/generated/drop.rs"] {
+            fs::write(root.join(file), "x").unwrap();
+        }
+
+        let walked = |respect: bool| -> Vec<String> {
+            let mut files: Vec<String> = walk_directory(root, &[], respect)
+                .filter(|e| e.file_type().is_some_and(|t| t.is_file()))
+                .map(|e| e.path().strip_prefix(root).unwrap().to_string_lossy().replace('\\', "/"))
+                .collect();
+            files.sort();
+            files
+        };
+
+        assert_eq!(walked(true), vec![".gitignore", ".mdignore", "main.rs", "src/generated/keep.rs", "src/lib.rs"]);
+        assert_eq!(walked(false).len(), 10);
     }
 }
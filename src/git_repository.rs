@@ -0,0 +1,546 @@
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use anyhow::{Context, Result};
+use chrono::{DateTime, FixedOffset, Local};
+use git2::{Commit, Delta, DiffOptions, Patch, Repository, Sort, StatusOptions, Tree};
+use crate::config::{GitFilter, GitMode};
+
+/// Revision of a project's repository when its documentation was generated.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GitMetadata {
+    /// Current branch, or `None` for a detached `HEAD`.
+    pub branch: Option<String>,
+    /// Id of the `HEAD` commit, or `None` if there are no commits yet.
+    pub head: Option<String>,
+    /// Whether the working tree has uncommitted or untracked changes.
+    pub dirty: bool,
+    /// Local time the metadata was read, e.g. "2024-05-01 14:03:22 +02:00".
+    pub generated_at: String,
+    /// Last commit that changed each documented file, by file path.
+    pub last_commits: HashMap<PathBuf, CommitInfo>,
+}
+
+/// Author and date of a commit.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CommitInfo {
+    /// Name of the commit author.
+    pub author: String,
+    /// Author date of the commit, e.g. "2024-05-01".
+    pub date: String,
+}
+
+impl CommitInfo {
+    fn from_commit(commit: &Commit) -> Self {
+        let signature = commit.author();
+        let when = signature.when();
+        let date = FixedOffset::east_opt(when.offset_minutes() * 60)
+            .and_then(|offset| DateTime::from_timestamp(when.seconds(), 0).map(|utc| utc.with_timezone(&offset)))
+            .map(|time| time.format("%Y-%m-%d").to_string())
+            .unwrap_or_default();
+        CommitInfo {
+            author: signature.name().unwrap_or_default().to_string(),
+            date,
+        }
+    }
+}
+
+/// How a file changed between two revisions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChangeStatus {
+    /// The file only exists in the new revision.
+    Added,
+    /// The file exists in both revisions with different content or type.
+    Modified,
+    /// The file only exists in the old revision.
+    Deleted,
+}
+
+/// Changes of a file between two revisions.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileDiff {
+    /// How the file changed.
+    pub status: ChangeStatus,
+    /// Unified diff hunks, each starting with its `@@` header, or `None` for a binary file.
+    /// Empty for a change of type or mode only, and for an empty added or deleted file.
+    pub hunks: Option<String>,
+}
+
+/// Opens the git repository containing a project, searching parent directories.
+///
+/// The repository is read locally; no remote is ever contacted.
+///
+/// # Arguments
+///
+/// * `project_path` - Path to the project root directory.
+///
+/// # Returns
+///
+/// * `Result<Repository>` - The repository or an error.
+pub fn open_repository(project_path: &Path) -> Result<Repository> {
+    Repository::discover(project_path)
+        .context(format!("Failed to find a git repository for: {}", project_path.display()))
+}
+
+/// Returns the canonical path of the working directory of a repository.
+fn workdir(repo: &Repository) -> Result<PathBuf> {
+    let workdir = repo
+        .workdir()
+        .context("Git repository has no working directory (bare repository)")?;
+    fs::canonicalize(workdir).context(format!("Failed to resolve path: {}", workdir.display()))
+}
+
+/// Resolves the tree of the common ancestor of a ref and `HEAD`.
+///
+/// Comparing against the merge base leaves out changes made on the other branch after
+/// the current branch forked from it.
+fn merge_base_tree<'r>(repo: &'r Repository, rev: &str) -> Result<Tree<'r>> {
+    let commit = repo
+        .revparse_single(rev)
+        .and_then(|object| object.peel_to_commit())
+        .context(format!("Failed to resolve git revision: {}", rev))?;
+    let base = match repo.head().ok().and_then(|head| head.target()) {
+        Some(head) => repo
+            .merge_base(head, commit.id())
+            .and_then(|oid| repo.find_commit(oid))
+            .unwrap_or(commit),
+        None => commit,
+    };
+    base.tree().context(format!("Failed to read the tree of git revision: {}", rev))
+}
+
+/// Selects files of the project's git repository.
+///
+/// # Arguments
+///
+/// * `project_path` - Path to the project root directory.
+/// * `filter` - Which files to select.
+///
+/// # Returns
+///
+/// * `Result<HashSet<PathBuf>>` - Canonical paths of the selected files, or an error.
+pub fn select_files(project_path: &Path, filter: &GitFilter) -> Result<HashSet<PathBuf>> {
+    let repo = open_repository(project_path)?;
+    let workdir = workdir(&repo)?;
+
+    let relative_paths: Vec<PathBuf> = match filter {
+        GitFilter::Mode(GitMode::Tracked) => {
+            let index = repo.index().context("Failed to read the git index")?;
+            index
+                .iter()
+                .map(|entry| PathBuf::from(String::from_utf8_lossy(&entry.path).into_owned()))
+                .collect()
+        }
+        GitFilter::ChangedSince { changed_since } => {
+            let tree = merge_base_tree(&repo, changed_since)?;
+            changed_files(&repo, Some(&tree))?
+        }
+        GitFilter::Mode(GitMode::WorkingTree) => {
+            // An unborn branch has no HEAD tree; every file is then a change
+            let head_tree = repo.head().ok().and_then(|head| head.peel_to_tree().ok());
+            changed_files(&repo, head_tree.as_ref())?
+        }
+    };
+
+    let selected: HashSet<PathBuf> = relative_paths.iter().map(|path| workdir.join(path)).collect();
+    log::info!("Selected {} files from git repository: {}", selected.len(), workdir.display());
+    Ok(selected)
+}
+
+/// Lists files of the working tree (with the index) that differ from a tree.
+///
+/// Deleted files are left out, and untracked files that are not ignored are included.
+fn changed_files(repo: &Repository, tree: Option<&Tree>) -> Result<Vec<PathBuf>> {
+    let mut options = DiffOptions::new();
+    options.include_untracked(true).recurse_untracked_dirs(true);
+
+    let diff = repo
+        .diff_tree_to_workdir_with_index(tree, Some(&mut options))
+        .context("Failed to compare the working tree with git")?;
+
+    Ok(diff
+        .deltas()
+        .filter(|delta| delta.status() != git2::Delta::Deleted)
+        .filter_map(|delta| delta.new_file().path().map(Path::to_path_buf))
+        .collect())
+}
+
+/// Reads the revision of a project's repository and the last commit of each file.
+///
+/// # Arguments
+///
+/// * `project_path` - Path to the project root directory.
+/// * `files` - Paths of the documented files.
+///
+/// # Returns
+///
+/// * `Result<GitMetadata>` - The metadata or an error.
+pub fn read_metadata<'a>(project_path: &Path, files: impl IntoIterator<Item = &'a PathBuf>) -> Result<GitMetadata> {
+    let repo = open_repository(project_path)?;
+    let workdir = workdir(&repo)?;
+
+    let head = repo.head().ok();
+    let branch = match &head {
+        Some(head) if head.is_branch() => head.shorthand().map(str::to_string),
+        Some(_) => None,
+        // An unborn branch has no commit yet, but HEAD still names it
+        None => repo
+            .find_reference("HEAD")
+            .ok()
+            .and_then(|reference| reference.symbolic_target().map(str::to_string))
+            .map(|target| target.trim_start_matches("refs/heads/").to_string()),
+    };
+    let head_commit = head.as_ref().and_then(|head| head.peel_to_commit().ok());
+
+    let mut options = StatusOptions::new();
+    options.include_untracked(true).include_ignored(false);
+    let dirty = !repo
+        .statuses(Some(&mut options))
+        .context("Failed to read the git status")?
+        .is_empty();
+
+    // Map the documented files tracked at HEAD to their path and blob in the repository
+    let mut pending: HashMap<PathBuf, (PathBuf, git2::Oid)> = HashMap::new();
+    if let Some(tree) = head_commit.as_ref().map(Commit::tree).transpose()? {
+        for file_path in files {
+            let Ok(canonical) = fs::canonicalize(file_path) else { continue };
+            let Ok(relative_path) = canonical.strip_prefix(&workdir) else { continue };
+            if let Ok(entry) = tree.get_path(relative_path) {
+                pending.insert(relative_path.to_path_buf(), (file_path.clone(), entry.id()));
+            }
+        }
+    }
+
+    // Walk the history from HEAD until the last commit of every file is known
+    let mut last_commits = HashMap::new();
+    if head_commit.is_some() && !pending.is_empty() {
+        let mut revwalk = repo.revwalk()?;
+        revwalk.push_head()?;
+        revwalk.set_sorting(Sort::TIME)?;
+        for oid in revwalk {
+            let commit = repo.find_commit(oid?)?;
+            let tree = commit.tree()?;
+            for path in changed_paths(&repo, &commit, &tree, pending.keys())? {
+                // A file changed on a branch whose version a later merge dropped was not
+                // last changed there; only the commit that wrote the HEAD version counts
+                let blob = tree.get_path(&path).ok().map(|entry| entry.id());
+                if blob == pending.get(&path).map(|(_, head_blob)| *head_blob) {
+                    if let Some((file_path, _)) = pending.remove(&path) {
+                        last_commits.insert(file_path, CommitInfo::from_commit(&commit));
+                    }
+                }
+            }
+            if pending.is_empty() {
+                break;
+            }
+        }
+    }
+
+    Ok(GitMetadata {
+        branch,
+        head: head_commit.map(|commit| commit.id().to_string()),
+        dirty,
+        generated_at: Local::now().format("%Y-%m-%d %H:%M:%S %:z").to_string(),
+        last_commits,
+    })
+}
+
+/// Returns the given paths changed by a commit.
+///
+/// A path is changed if it differs from every parent of the commit, so a merge that keeps
+/// a file from one of its parents does not change it (the TREESAME rule of `git log`),
+/// while a merge that resolves a conflict does.
+///
+/// # Arguments
+///
+/// * `repo` - The repository.
+/// * `commit` - The commit.
+/// * `tree` - The tree of the commit.
+/// * `paths` - Paths in the repository to compare.
+///
+/// # Returns
+///
+/// * `Result<HashSet<PathBuf>>` - The changed paths or an error.
+fn changed_paths<'a>(
+    repo: &Repository,
+    commit: &Commit,
+    tree: &Tree,
+    paths: impl IntoIterator<Item = &'a PathBuf>,
+) -> Result<HashSet<PathBuf>> {
+    let mut changed: HashSet<PathBuf> = paths.into_iter().cloned().collect();
+    let parent_trees = if commit.parent_count() == 0 {
+        vec![None]
+    } else {
+        commit.parents().map(|parent| parent.tree().map(Some)).collect::<Result<Vec<_>, _>>()?
+    };
+    for parent_tree in parent_trees {
+        if changed.is_empty() {
+            break;
+        }
+        // Only diff the remaining paths, matched literally
+        let mut options = DiffOptions::new();
+        options.disable_pathspec_match(true);
+        for path in &changed {
+            options.pathspec(path.as_path());
+        }
+        let diff = repo.diff_tree_to_tree(parent_tree.as_ref(), Some(tree), Some(&mut options))?;
+        changed = diff
+            .deltas()
+            .filter_map(|delta| delta.new_file().path().or(delta.old_file().path()).map(Path::to_path_buf))
+            .filter(|path| changed.contains(path))
+            .collect();
+    }
+    Ok(changed)
+}
+
+/// Compares two revisions of a project's repository.
+///
+/// Only files under the project root are compared. Renamed files are reported as a
+/// deleted and an added file.
+///
+/// # Arguments
+///
+/// * `project_path` - Path to the project root directory.
+/// * `from` - Old revision (a ref or commit).
+/// * `to` - New revision (a ref or commit).
+///
+/// # Returns
+///
+/// * `Result<Vec<(PathBuf, FileDiff)>>` - The changed files, by path under `project_path`,
+///   or an error.
+pub fn diff_revisions(project_path: &Path, from: &str, to: &str) -> Result<Vec<(PathBuf, FileDiff)>> {
+    let repo = open_repository(project_path)?;
+    let workdir = workdir(&repo)?;
+    let project_root = fs::canonicalize(project_path)
+        .context(format!("Failed to resolve path: {}", project_path.display()))?;
+    let project_prefix = project_root.strip_prefix(&workdir).unwrap_or(Path::new(""));
+
+    let revision_tree = |rev: &str| {
+        repo.revparse_single(rev)
+            .and_then(|object| object.peel_to_tree())
+            .context(format!("Failed to resolve git revision: {}", rev))
+    };
+    let old_tree = revision_tree(from)?;
+    let new_tree = revision_tree(to)?;
+
+    let mut options = DiffOptions::new();
+    if !project_prefix.as_os_str().is_empty() {
+        options.pathspec(project_prefix);
+    }
+    let diff = repo
+        .diff_tree_to_tree(Some(&old_tree), Some(&new_tree), Some(&mut options))
+        .context(format!("Failed to compare git revisions: {}..{}", from, to))?;
+
+    let mut files = Vec::new();
+    for (index, delta) in diff.deltas().enumerate() {
+        let status = match delta.status() {
+            Delta::Added => ChangeStatus::Added,
+            Delta::Deleted => ChangeStatus::Deleted,
+            Delta::Modified | Delta::Typechange => ChangeStatus::Modified,
+            _ => continue,
+        };
+        let Some(path) = delta.new_file().path().or(delta.old_file().path()) else { continue };
+        let Ok(relative_path) = path.strip_prefix(project_prefix) else { continue };
+
+        let patch = Patch::from_diff(&diff, index)?;
+        let hunks = match patch {
+            Some(patch) if !patch.delta().flags().is_binary() => Some(render_hunks(&patch)?),
+            _ => None,
+        };
+        files.push((project_path.join(relative_path), FileDiff { status, hunks }));
+    }
+
+    log::info!("Found {} changed files between {} and {} in: {}", files.len(), from, to, project_root.display());
+    Ok(files)
+}
+
+/// Renders the hunks of a patch as unified diff text, without the file headers.
+fn render_hunks(patch: &Patch) -> Result<String> {
+    let mut text = String::new();
+    for hunk_index in 0..patch.num_hunks() {
+        let (hunk, line_count) = patch.hunk(hunk_index)?;
+        text.push_str(&String::from_utf8_lossy(hunk.header()));
+        for line_index in 0..line_count {
+            let line = patch.line_in_hunk(hunk_index, line_index)?;
+            let content = String::from_utf8_lossy(line.content());
+            match line.origin() {
+                origin @ ('+' | '-' | ' ') => {
+                    text.push(origin);
+                    text.push_str(&content);
+                }
+                // Missing newline at the end of the old or new file
+                _ => text.push_str(content.trim_start_matches('\n')),
+            }
+            if !text.ends_with('\n') {
+                text.push('\n');
+            }
+        }
+    }
+    Ok(text)
+}
+
+/// Returns `true` if a file is part of a git selection.
+///
+/// # Arguments
+///
+/// * `file_path` - Path to the file.
+/// * `selection` - Canonical paths returned by [`select_files`].
+pub fn is_selected(file_path: &Path, selection: &HashSet<PathBuf>) -> bool {
+    fs::canonicalize(file_path).is_ok_and(|path| selection.contains(&path))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    /// Stages all files of a repository and commits them.
+    fn commit_all(repo: &Repository, message: &str) {
+        let mut index = repo.index().unwrap();
+        index.add_all(["*"], git2::IndexAddOption::DEFAULT, None).unwrap();
+        index.write().unwrap();
+        let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+        let signature = git2::Signature::now("Test", "test@example.com").unwrap();
+        let parents: Vec<git2::Commit> = repo.head().ok().and_then(|h| h.peel_to_commit().ok()).into_iter().collect();
+        let parent_refs: Vec<&git2::Commit> = parents.iter().collect();
+        repo.commit(Some("HEAD"), &signature, &signature, message, &tree, &parent_refs).unwrap();
+    }
+
+    #[test]
+    fn test_select_files() {
+        let temp = tempdir().unwrap();
+        let root = fs::canonicalize(temp.path()).unwrap();
+        let repo = Repository::init(&root).unwrap();
+
+        fs::write(root.join("a.rs"), "a").unwrap();
+        fs::write(root.join("b.rs"), "b").unwrap();
+        commit_all(&repo, "first");
+        repo.tag_lightweight("v1", &repo.head().unwrap().peel(git2::ObjectType::Commit).unwrap(), false).unwrap();
+
+        fs::write(root.join("b.rs"), "b2").unwrap();
+        fs::write(root.join("c.rs"), "c").unwrap();
+        commit_all(&repo, "second");
+
+        fs::write(root.join("a.rs"), "a2").unwrap();
+        fs::write(root.join("untracked.rs"), "u").unwrap();
+
+        let select = |filter: GitFilter| {
+            let mut names: Vec<String> = select_files(&root, &filter)
+                .unwrap()
+                .iter()
+                .map(|p| p.strip_prefix(&root).unwrap().to_string_lossy().into_owned())
+                .collect();
+            names.sort();
+            names
+        };
+
+        let changed_since = |rev: &str| GitFilter::ChangedSince { changed_since: rev.to_string() };
+        assert_eq!(select(GitFilter::Mode(GitMode::Tracked)), vec!["a.rs", "b.rs", "c.rs"]);
+        assert_eq!(select(GitFilter::Mode(GitMode::WorkingTree)), vec!["a.rs", "untracked.rs"]);
+        assert_eq!(select(changed_since("v1")), vec!["a.rs", "b.rs", "c.rs", "untracked.rs"]);
+        assert!(select_files(&root, &changed_since("missing")).is_err());
+    }
+
+    #[test]
+    fn test_read_metadata() {
+        let temp = tempdir().unwrap();
+        let root = fs::canonicalize(temp.path()).unwrap();
+        let repo = Repository::init(&root).unwrap();
+
+        let unborn = read_metadata(&root, []).unwrap();
+        assert_eq!(unborn.head, None);
+        assert!(!unborn.dirty);
+
+        fs::write(root.join("a.rs"), "a").unwrap();
+        fs::write(root.join("b.rs"), "b").unwrap();
+        commit_all(&repo, "first");
+        fs::write(root.join("b.rs"), "b2").unwrap();
+        commit_all(&repo, "second");
+        fs::write(root.join("new.rs"), "n").unwrap();
+
+        let files = [root.join("a.rs"), root.join("b.rs"), root.join("new.rs")];
+        let metadata = read_metadata(&root, &files).unwrap();
+        let head = repo.head().unwrap();
+        assert_eq!(metadata.branch.as_deref(), head.shorthand());
+        assert_eq!(metadata.head, Some(head.target().unwrap().to_string()));
+        assert!(metadata.dirty);
+        assert_eq!(metadata.last_commits.len(), 2);
+        assert_eq!(metadata.last_commits[&files[0]].author, "Test");
+        assert!(!metadata.last_commits.contains_key(&files[2]));
+    }
+
+    #[test]
+    fn test_read_metadata_with_merge() {
+        let temp = tempdir().unwrap();
+        let root = fs::canonicalize(temp.path()).unwrap();
+        let repo = Repository::init(&root).unwrap();
+
+        // Commits files with the given content, by an author at a given time
+        let commit = |files: &[(&str, &str)], author: &str, seconds: i64, parents: &[&git2::Commit], head: bool| {
+            for (name, content) in files {
+                fs::write(root.join(name), content).unwrap();
+            }
+            let mut index = repo.index().unwrap();
+            index.add_all(["*"], git2::IndexAddOption::DEFAULT, None).unwrap();
+            index.write().unwrap();
+            let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+            let signature = git2::Signature::new(author, "test@example.com", &git2::Time::new(seconds, 0)).unwrap();
+            let update_ref = if head { Some("HEAD") } else { None };
+            let oid = repo.commit(update_ref, &signature, &signature, author, &tree, parents).unwrap();
+            repo.find_commit(oid).unwrap()
+        };
+
+        let base = [("a.rs", "a"), ("b.rs", "b"), ("c.rs", "c"), ("d.rs", "d")];
+        let first = commit(&base, "First", 1_700_000_000, &[], true);
+        let feature = commit(&[("b.rs", "b2"), ("c.rs", "c2"), ("d.rs", "d2")], "Feature", 1_700_000_100, &[&first], false);
+        let main = commit(&[("a.rs", "a2"), ("b.rs", "b"), ("c.rs", "c3"), ("d.rs", "d")], "Main", 1_700_000_200, &[&first], true);
+        // The merge takes b.rs from the feature branch, resolves a conflict in c.rs and
+        // drops the feature branch change to d.rs
+        commit(&[("b.rs", "b2"), ("c.rs", "c4"), ("d.rs", "d")], "Merger", 1_700_000_300, &[&main, &feature], true);
+
+        let files = base.map(|(name, _)| root.join(name));
+        let metadata = read_metadata(&root, &files).unwrap();
+        assert_eq!(metadata.last_commits[&files[0]].author, "Main");
+        assert_eq!(metadata.last_commits[&files[1]].author, "Feature");
+        assert_eq!(metadata.last_commits[&files[2]].author, "Merger");
+        assert_eq!(metadata.last_commits[&files[3]].author, "First");
+    }
+
+    #[test]
+    fn test_diff_revisions() {
+        let temp = tempdir().unwrap();
+        let root = fs::canonicalize(temp.path()).unwrap();
+        let repo = Repository::init(&root).unwrap();
+
+        fs::create_dir(root.join("src")).unwrap();
+        fs::write(root.join("src/a.rs"), "one\ntwo\nthree\n").unwrap();
+        fs::write(root.join("src/old.rs"), "old\n").unwrap();
+        fs::write(root.join("outside.rs"), "x\n").unwrap();
+        commit_all(&repo, "first");
+        repo.tag_lightweight("v1", &repo.head().unwrap().peel(git2::ObjectType::Commit).unwrap(), false).unwrap();
+
+        fs::write(root.join("src/a.rs"), "one\n2\nthree").unwrap();
+        fs::remove_file(root.join("src/old.rs")).unwrap();
+        fs::write(root.join("src/new.bin"), [0u8, 1, 2]).unwrap();
+        fs::write(root.join("outside.rs"), "y\n").unwrap();
+        let mut index = repo.index().unwrap();
+        index.remove_path(Path::new("src/old.rs")).unwrap();
+        index.write().unwrap();
+        commit_all(&repo, "second");
+
+        let project = root.join("src");
+        let diffs = diff_revisions(&project, "v1", "HEAD").unwrap();
+        let statuses: Vec<(PathBuf, ChangeStatus)> = diffs.iter().map(|(path, diff)| (path.clone(), diff.status)).collect();
+        assert_eq!(statuses, vec![
+            (project.join("a.rs"), ChangeStatus::Modified),
+            (project.join("new.bin"), ChangeStatus::Added),
+            (project.join("old.rs"), ChangeStatus::Deleted),
+        ]);
+        assert_eq!(
+            diffs[0].1.hunks.as_deref(),
+            Some("@@ -1,3 +1,3 @@\n one\n-two\n-three\n+2\n+three\n\\ No newline at end of file\n")
+        );
+        assert_eq!(diffs[1].1.hunks, None);
+        assert!(diff_revisions(&project, "missing", "HEAD").is_err());
+    }
+}
@@ -0,0 +1,518 @@
+use std::path::{Path, PathBuf};
+use std::collections::HashMap;
+use std::sync::LazyLock;
+use anyhow::Result;
+use syntect::highlighting::ThemeSet;
+use syntect::html::{css_for_theme_with_class_style, ClassStyle, ClassedHTMLGenerator};
+use syntect::parsing::{SyntaxReference, SyntaxSet};
+use syntect::util::LinesWithEndings;
+use crate::config::Config;
+use crate::file_processor::FileContent;
+use crate::git_repository::GitMetadata;
+use crate::language::{Language, Languages};
+use crate::markdown_generator::{
+    commit_annotation, excerpt_lines, first_line, github_anchor, heading_anchors, line_number_width, localized_text,
+    number_line, project_title,
+};
+use crate::statistics::ProjectStats;
+use crate::token_counter::TokenStats;
+use crate::{language, tree_generator};
+
+/// Syntax definitions bundled with syntect, loaded once.
+static SYNTAX_SET: LazyLock<SyntaxSet> = LazyLock::new(SyntaxSet::load_defaults_newlines);
+
+/// Themes bundled with syntect, loaded once.
+static THEME_SET: LazyLock<ThemeSet> = LazyLock::new(ThemeSet::load_defaults);
+
+/// Name of the bundled theme used for syntax highlighting.
+const HIGHLIGHT_THEME: &str = "InspiredGitHub";
+
+/// Class style shared by the highlighter and the generated theme CSS.
+const CLASS_STYLE: ClassStyle = ClassStyle::SpacedPrefixed { prefix: "hl-" };
+
+/// Base stylesheet embedded in every generated page.
+const BASE_CSS: &str = r#"
+* { box-sizing: border-box; }
+body { margin: 0; font-family: -apple-system, "Segoe UI", Helvetica, Arial, sans-serif; color: #1f2328; display: flex; }
+nav.sidebar { position: sticky; top: 0; height: 100vh; overflow: auto; width: 320px; flex-shrink: 0; padding: 16px; background: #f6f8fa; border-right: 1px solid #d0d7de; font-size: 14px; }
+nav.sidebar ul { list-style: none; margin: 0; padding-left: 16px; }
+nav.sidebar > ul { padding-left: 0; }
+nav.sidebar summary { cursor: pointer; font-weight: 600; }
+nav.sidebar a { color: #0969da; text-decoration: none; }
+nav.sidebar a:hover { text-decoration: underline; }
+nav.sidebar .marker { color: #656d76; }
+main { flex-grow: 1; min-width: 0; padding: 16px 32px; }
+section.file { margin-bottom: 32px; }
+section.file h3 { border-bottom: 1px solid #d0d7de; padding-bottom: 4px; }
+pre.code { padding: 16px; overflow: auto; background: #f6f8fa; border-radius: 6px; font-size: 13px; line-height: 1.45; }
+p.binary, ul.omitted { color: #656d76; }
+span.line-number { color: #8c959f; user-select: none; }
+ul.metadata { padding-left: 20px; }
+table { border-collapse: collapse; margin-bottom: 16px; font-size: 14px; }
+th, td { border: 1px solid #d0d7de; padding: 4px 12px; text-align: left; }
+th.number, td.number { text-align: right; }
+"#;
+
+/// Generates a self-contained HTML page for a project.
+///
+/// The page embeds its stylesheet, highlights source code at generation time and shows
+/// the project tree in a collapsible sidebar linking to each file section. It has the
+/// sections of the Markdown output, with the same headings and anchors; the tree takes
+/// the place of the table of contents.
+///
+/// # Arguments
+///
+/// * `config` - The project configuration.
+/// * `files` - List of files with their paths and contents.
+/// * `languages` - Language definitions.
+/// * `token_stats` - Token counts to summarize at the top of the page (optional).
+/// * `git_metadata` - Repository revision to describe in the header (optional).
+///
+/// # Returns
+///
+/// * `Result<String>` - The generated HTML content or an error.
+pub fn generate_html(
+    config: &Config,
+    files: Vec<(PathBuf, FileContent)>,
+    languages: &Languages,
+    token_stats: Option<&TokenStats>,
+    git_metadata: Option<&GitMetadata>,
+) -> Result<String> {
+    let project_name = config.project_name.as_str();
+    let project_root = config.project_path.as_path();
+    let lang = config.markdown_lang.as_str();
+
+    // Sort files for consistent output
+    let mut sorted_files = files;
+    sorted_files.sort_by(|a, b| a.0.cmp(&b.0));
+
+    let statistics = config.statistics.then(|| ProjectStats::collect(&sorted_files, languages));
+    let title = project_title(config);
+    let tree_heading = localized_text("project_file_tree", lang);
+    let files_heading = localized_text("project_files", lang);
+    let file_label = localized_text("file_label", lang);
+    let binary_label = localized_text("binary_file", lang);
+    let bytes_label = localized_text("bytes", lang);
+
+    // Use the same headings and anchors as the Markdown output so links are interchangeable
+    let mut used_anchors = heading_anchors(config, &title, token_stats.is_some(), statistics.is_some());
+    let mut anchors = HashMap::new();
+    let mut headings = HashMap::new();
+    for (file_path, content) in &sorted_files {
+        // Omitted files have no section; they are listed under "Omitted Files"
+        if let FileContent::Omitted { .. } = content {
+            continue;
+        }
+        let relative_path = file_path.strip_prefix(project_root).unwrap_or(file_path);
+        let range = excerpt_lines(config, file_path, content)
+            .map(|(first_line, last_line)| format!(" (L{}-{})", first_line, last_line))
+            .unwrap_or_default();
+        let annotation = commit_annotation(git_metadata, file_path);
+        let heading = format!("{}: `{}`{}{}", file_label, relative_path.display(), range, annotation);
+        anchors.insert(file_path.clone(), github_anchor(&heading, &mut used_anchors));
+        headings.insert(file_path.clone(), format!(
+            "{}: <code>{}</code>{}",
+            escape_html(&file_label),
+            escape_html(&relative_path.display().to_string()),
+            escape_html(&format!("{}{}", range, annotation))
+        ));
+    }
+
+    let theme_css = css_for_theme_with_class_style(&THEME_SET.themes[HIGHLIGHT_THEME], CLASS_STYLE)?;
+    let html_lang = if lang == "zh_cn" { "zh-CN" } else { "en" };
+
+    let mut html = String::new();
+    html.push_str(&format!(
+        "<!DOCTYPE html>\n<html lang=\"{}\">\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n<style>{}{}</style>\n</head>\n<body>\n",
+        html_lang,
+        escape_html(&title),
+        BASE_CSS,
+        theme_css
+    ));
+
+    // Sidebar with the collapsible project tree
+    html.push_str(&format!("<nav class=\"sidebar\">\n<h2>{}</h2>\n", escape_html(&tree_heading)));
+    html.push_str(&tree_generator::generate_html_tree(project_name, &sorted_files, project_root, &anchors)?);
+    html.push_str("</nav>\n<main>\n");
+
+    html.push_str(&format!("<h1>{}</h1>\n", escape_html(&title)));
+    if let Some(metadata) = git_metadata {
+        html.push_str(&render_git_metadata(config, metadata));
+    }
+    if let Some(stats) = token_stats {
+        html.push_str(&render_token_summary(config, stats));
+    }
+    if let Some(stats) = &statistics {
+        html.push_str(&render_statistics(config, stats));
+    }
+
+    html.push_str(&format!("<h2>{}</h2>\n", escape_html(&files_heading)));
+    for (file_path, content) in &sorted_files {
+        let body = match content {
+            FileContent::Text(text) => {
+                let language = language::detect_language(file_path, Some(text), languages);
+                let highlighted = highlight(text, file_path, &language)?;
+                let code = if config.line_numbers {
+                    number_lines(&highlighted, first_line(config, file_path), text)
+                } else {
+                    highlighted
+                };
+                format!("<pre class=\"code\"><code>{}</code></pre>\n", code)
+            }
+            FileContent::Binary(info) => format!(
+                "<p class=\"binary\">{}: {} {}, <code>{}</code>, SHA-256 <code>{}</code></p>\n",
+                escape_html(&binary_label),
+                info.size,
+                escape_html(&bytes_label),
+                info.mime_type,
+                info.sha256
+            ),
+            FileContent::Omitted { .. } => continue,
+        };
+        html.push_str(&format!(
+            "<section class=\"file\" id=\"{}\">\n<h3>{}</h3>\n{}</section>\n",
+            anchors[file_path], headings[file_path], body
+        ));
+    }
+
+    html.push_str(&render_omitted_files(config, &sorted_files));
+    html.push_str("</main>\n</body>\n</html>\n");
+    Ok(html)
+}
+
+/// Prefixes each line of highlighted code with its number.
+///
+/// # Arguments
+///
+/// * `highlighted` - The highlighted HTML, with one line per source line.
+/// * `first_line` - Number of the first line.
+/// * `text` - The source code, used to size the line numbers.
+///
+/// # Returns
+///
+/// * `String` - The numbered HTML.
+fn number_lines(highlighted: &str, first_line: usize, text: &str) -> String {
+    let width = line_number_width(first_line, text);
+    let line_count = text.split_inclusive('\n').count();
+    highlighted
+        .split_inclusive('\n')
+        .enumerate()
+        .map(|(index, line)| {
+            // Tags closed after the last newline are not a line of their own
+            if index >= line_count {
+                return line.to_string();
+            }
+            // Highlighted spans may continue across lines; the number sets its own color
+            let number = number_line(first_line + index, width, "");
+            format!("<span class=\"line-number\">{}</span>{}", number, line)
+        })
+        .collect()
+}
+
+/// Renders the repository revision as a list of fields.
+fn render_git_metadata(config: &Config, metadata: &GitMetadata) -> String {
+    let lang = config.markdown_lang.as_str();
+    let code = |value: &str| format!("<code>{}</code>", escape_html(value));
+    let branch = metadata
+        .branch
+        .as_deref()
+        .map_or_else(|| escape_html(&localized_text("detached_head", lang)), code);
+    let commit = metadata
+        .head
+        .as_deref()
+        .map_or_else(|| escape_html(&localized_text("no_commits", lang)), code);
+    let status = localized_text(if metadata.dirty { "dirty" } else { "clean" }, lang);
+
+    let fields = [
+        ("branch", branch),
+        ("commit", commit),
+        ("working_tree", escape_html(&status)),
+        ("generated_at", escape_html(&metadata.generated_at)),
+    ];
+    let mut list = String::from("<ul class=\"metadata\">\n");
+    for (key, value) in fields {
+        list.push_str(&format!("<li><strong>{}</strong>: {}</li>\n", escape_html(&localized_text(key, lang)), value));
+    }
+    list.push_str("</ul>\n");
+    list
+}
+
+/// Renders the token summary table, largest files first.
+fn render_token_summary(config: &Config, stats: &TokenStats) -> String {
+    let project_root = config.project_path.as_path();
+    let lang = config.markdown_lang.as_str();
+
+    let mut summary = format!("<h2>{}</h2>\n", escape_html(&localized_text("token_summary", lang)));
+    summary.push_str(&format!(
+        "<p>{}: <code>{}</code>, {}: <strong>{}</strong></p>\n",
+        escape_html(&localized_text("encoding", lang)),
+        stats.encoding.name(),
+        escape_html(&localized_text("total", lang)),
+        stats.total
+    ));
+    let mut file_tokens: Vec<&(PathBuf, usize)> = stats.files.iter().collect();
+    file_tokens.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    let rows = file_tokens.into_iter().map(|(file_path, tokens)| {
+        let relative_path = file_path.strip_prefix(project_root).unwrap_or(file_path);
+        vec![code_cell(relative_path), tokens.to_string()]
+    });
+    summary.push_str(&render_table(lang, &[("file_label", false), ("tokens", true)], rows));
+    summary
+}
+
+/// Renders the statistics table per language, followed by the largest files.
+fn render_statistics(config: &Config, stats: &ProjectStats) -> String {
+    let project_root = config.project_path.as_path();
+    let lang = config.markdown_lang.as_str();
+
+    let mut html = format!("<h2>{}</h2>\n", escape_html(&localized_text("statistics", lang)));
+    let total = stats.total();
+    let rows = stats
+        .languages
+        .iter()
+        .map(|row| (escape_html(&row.language), row))
+        .chain(std::iter::once((format!("<strong>{}</strong>", escape_html(&localized_text("total", lang))), &total)))
+        .map(|(label, row)| vec![
+            label,
+            row.files.to_string(),
+            row.lines.total().to_string(),
+            row.lines.code.to_string(),
+            row.lines.comment.to_string(),
+            row.lines.blank.to_string(),
+            row.bytes.to_string(),
+        ]);
+    let columns = [
+        ("language", false),
+        ("files", true),
+        ("lines", true),
+        ("code_lines", true),
+        ("comment_lines", true),
+        ("blank_lines", true),
+        ("size", true),
+    ];
+    html.push_str(&render_table(lang, &columns, rows));
+
+    html.push_str(&format!("<h3>{}</h3>\n", escape_html(&localized_text("largest_files", lang))));
+    let rows = stats.largest_files.iter().map(|file| {
+        let relative_path = file.path.strip_prefix(project_root).unwrap_or(&file.path);
+        vec![code_cell(relative_path), escape_html(&file.language), file.lines.to_string(), file.bytes.to_string()]
+    });
+    let columns = [("file_label", false), ("language", false), ("lines", true), ("size", true)];
+    html.push_str(&render_table(lang, &columns, rows));
+    html
+}
+
+/// Renders the list of files omitted to fit the content budget, if any.
+fn render_omitted_files(config: &Config, files: &[(PathBuf, FileContent)]) -> String {
+    let project_root = config.project_path.as_path();
+    let lang = config.markdown_lang.as_str();
+
+    let omitted: Vec<(&PathBuf, u64)> = files
+        .iter()
+        .filter_map(|(file_path, content)| match content {
+            FileContent::Omitted { size } => Some((file_path, *size)),
+            _ => None,
+        })
+        .collect();
+    if omitted.is_empty() {
+        return String::new();
+    }
+
+    let mut list = format!(
+        "<h2>{}</h2>\n<p>{}</p>\n<ul class=\"omitted\">\n",
+        escape_html(&localized_text("omitted_files", lang)),
+        escape_html(&localized_text("omitted_intro", lang))
+    );
+    for (file_path, size) in omitted {
+        let relative_path = file_path.strip_prefix(project_root).unwrap_or(file_path);
+        list.push_str(&format!(
+            "<li>{} ({} {})</li>\n",
+            code_cell(relative_path),
+            size,
+            escape_html(&localized_text("bytes", lang))
+        ));
+    }
+    list.push_str("</ul>\n");
+    list
+}
+
+/// Renders a table with localized column headings; numeric columns are right-aligned.
+///
+/// # Arguments
+///
+/// * `lang` - Language of the headings.
+/// * `columns` - Localization key of each column heading, and whether it is numeric.
+/// * `rows` - Cells of each row, as HTML.
+///
+/// # Returns
+///
+/// * `String` - The HTML table.
+fn render_table(lang: &str, columns: &[(&str, bool)], rows: impl Iterator<Item = Vec<String>>) -> String {
+    let class = |numeric: bool| if numeric { " class=\"number\"" } else { "" };
+    let mut table = String::from("<table>\n<tr>");
+    for (key, numeric) in columns {
+        table.push_str(&format!("<th{}>{}</th>", class(*numeric), escape_html(&localized_text(key, lang))));
+    }
+    table.push_str("</tr>\n");
+    for row in rows {
+        table.push_str("<tr>");
+        for ((_, numeric), cell) in columns.iter().zip(row) {
+            table.push_str(&format!("<td{}>{}</td>", class(*numeric), cell));
+        }
+        table.push_str("</tr>\n");
+    }
+    table.push_str("</table>\n");
+    table
+}
+
+/// Renders a path as inline code.
+fn code_cell(path: &Path) -> String {
+    format!("<code>{}</code>", escape_html(&path.display().to_string()))
+}
+
+/// Highlights source code as HTML with CSS classes.
+///
+/// # Arguments
+///
+/// * `content` - The source code.
+/// * `file_path` - Path to the file, used to find a syntax by extension.
+/// * `language` - Language of the file, used as a fallback to find the syntax.
+///
+/// # Returns
+///
+/// * `Result<String>` - The highlighted HTML or an error.
+fn highlight(content: &str, file_path: &Path, language: &Language) -> Result<String> {
+    let syntax = find_syntax(file_path, language);
+    let mut generator = ClassedHTMLGenerator::new_with_class_style(syntax, &SYNTAX_SET, CLASS_STYLE);
+    for line in LinesWithEndings::from(content) {
+        generator.parse_html_for_line_which_includes_newline(line)?;
+    }
+    Ok(generator.finalize())
+}
+
+/// Finds the syntax definition for a file, falling back to plain text.
+fn find_syntax(file_path: &Path, language: &Language) -> &'static SyntaxReference {
+    let extension = file_path
+        .extension()
+        .unwrap_or_default()
+        .to_string_lossy();
+
+    SYNTAX_SET
+        .find_syntax_by_extension(&extension)
+        .or_else(|| SYNTAX_SET.find_syntax_by_token(&language.fence))
+        .or_else(|| SYNTAX_SET.find_syntax_by_token(&language.name))
+        .unwrap_or_else(|| SYNTAX_SET.find_syntax_plain_text())
+}
+
+/// Escapes text for use in HTML content and attribute values.
+pub fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{test_config, TokenEncoding};
+    use crate::git_repository::CommitInfo;
+    use crate::markdown_generator::generate_markdown;
+
+    #[test]
+    fn test_generate_html() {
+        let config = test_config("output_file: demo.html");
+        let files = vec![(
+            PathBuf::from("/project/src/main.rs"),
+            FileContent::Text("fn main() { println!(\"<hi>\"); }".to_string()),
+        )];
+
+        let html = generate_html(&config, files, &Languages::default(), None, None).unwrap();
+        assert!(html.contains("<li><a href=\"#file-srcmainrs\">main.rs</a></li>"));
+        assert!(html.contains("<section class=\"file\" id=\"file-srcmainrs\">\n<h3>File: <code>src/main.rs</code></h3>\n"));
+        assert!(html.contains("&lt;hi&gt;"));
+        assert!(!html.contains("<hi>"));
+    }
+
+    #[test]
+    fn test_generate_html_with_line_numbers() {
+        let root = Path::new("/project");
+        let files = vec![
+            (root.join("src/main.rs"), FileContent::Text("fn a() {}\n/* b\n */\n".to_string())),
+            (root.join("notes.txt"), FileContent::Text("note".to_string())),
+        ];
+        let mut config = test_config("line_numbers: true");
+        config.files = vec![serde_yaml::from_str("src/main.rs#L9-12").unwrap()];
+
+        let html = generate_html(&config, files, &language::default_languages(), None, None).unwrap();
+        assert!(html.contains("<section class=\"file\" id=\"file-srcmainrs-l9-11\">\n<h3>File: <code>src/main.rs</code> (L9-11)</h3>\n"));
+        let numbers: Vec<&str> = html.matches("<span class=\"line-number\">").collect();
+        assert_eq!(numbers.len(), 4);
+        assert!(html.contains("<span class=\"line-number\"> 9 | </span>"));
+        assert!(html.contains("<span class=\"line-number\">11 | </span>"));
+        assert!(html.contains("<span class=\"line-number\">1 | </span><span class=\"hl-text hl-plain\">note</span>"));
+    }
+
+    #[test]
+    fn test_generate_html_with_omitted_files() {
+        let root = Path::new("/project");
+        let files = vec![
+            (root.join("src/main.rs"), FileContent::Text("fn main() {}".to_string())),
+            (root.join("data.csv"), FileContent::Omitted { size: 2048 }),
+        ];
+
+        let html = generate_html(&test_config(""), files, &Languages::default(), None, None).unwrap();
+        assert!(!html.contains("<code>data.csv</code></h3>"));
+        assert!(!html.contains("class=\"binary\""));
+        assert!(html.contains("<h2>Omitted Files</h2>\n"));
+        assert!(html.contains("<ul class=\"omitted\">\n<li><code>data.csv</code> (2048 bytes)</li>\n</ul>\n"));
+    }
+
+    #[test]
+    fn test_generate_html_with_markdown_sections() {
+        let root = Path::new("/project");
+        let files = vec![
+            (root.join("a.rs"), FileContent::Text("// a\nfn a() {}\n".to_string())),
+            (root.join("b.rs"), FileContent::Text("b".to_string())),
+        ];
+        let commit = CommitInfo { author: "Alice".to_string(), date: "2024-05-01".to_string() };
+        let metadata = GitMetadata {
+            branch: Some("main".to_string()),
+            head: None,
+            dirty: false,
+            generated_at: "2024-05-02 10:00:00 +00:00".to_string(),
+            last_commits: HashMap::from([(root.join("a.rs"), commit)]),
+        };
+        let token_stats = TokenStats {
+            encoding: TokenEncoding::Estimate,
+            files: vec![(root.join("a.rs"), 4), (root.join("b.rs"), 1)],
+            total: 5,
+        };
+        let config = test_config("statistics: true");
+        let languages = language::default_languages();
+
+        let html = generate_html(&config, files.clone(), &languages, Some(&token_stats), Some(&metadata)).unwrap();
+        assert!(html.contains("<li><strong>Branch</strong>: <code>main</code></li>\n<li><strong>Commit</strong>: no commits yet</li>\n"));
+        assert!(html.contains("<h2>Token Summary</h2>\n<p>Encoding: <code>estimate</code>, Total: <strong>5</strong></p>\n"));
+        assert!(html.contains("<tr><td><code>a.rs</code></td><td class=\"number\">4</td></tr>\n"));
+        assert!(html.contains("<h2>Statistics</h2>\n<table>\n<tr><th>Language</th><th class=\"number\">Files</th>"));
+        assert!(html.contains("<tr><td>Rust</td><td class=\"number\">2</td><td class=\"number\">3</td>"));
+        assert!(html.contains("<h3>Largest Files</h3>\n"));
+        assert!(html.contains("<h3>File: <code>a.rs</code> — Alice, 2024-05-01</h3>"));
+
+        // Anchors match the Markdown output, after the same sections
+        let markdown = generate_markdown(&config, files, &languages, Some(&token_stats), Some(&metadata)).unwrap();
+        for anchor in ["file-ars--alice-2024-05-01", "file-brs"] {
+            assert!(markdown.contains(&format!("(#{})", anchor)));
+            assert!(html.contains(&format!("<section class=\"file\" id=\"{}\">", anchor)));
+        }
+    }
+}
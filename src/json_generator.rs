@@ -0,0 +1,210 @@
+use std::path::PathBuf;
+use anyhow::Result;
+use serde::Serialize;
+use crate::binary_detector;
+use crate::config::Config;
+use crate::file_processor::FileContent;
+use crate::language::Languages;
+use crate::{language, tree_generator};
+
+/// Snapshot of a project, serialized as a single JSON document.
+#[derive(Serialize)]
+struct ProjectSnapshot<'a> {
+    project_name: &'a str,
+    tree: String,
+    files: Vec<FileRecord<'a>>,
+}
+
+/// A record of the JSON Lines output.
+#[derive(Serialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+enum JsonlRecord<'a> {
+    /// Header record, always the first line.
+    Project { project_name: &'a str, tree: String },
+    /// One record per file.
+    File(FileRecord<'a>),
+}
+
+/// Description of a single file in the snapshot.
+#[derive(Serialize)]
+struct FileRecord<'a> {
+    /// Path relative to the project root, with `/` separators.
+    path: String,
+    language: String,
+    /// Size in bytes (of the UTF-8 text for transcoded files).
+    size: u64,
+    line_count: usize,
+    /// Content hash, absent for omitted files.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    sha256: Option<String>,
+    binary: bool,
+    /// `true` for files omitted to fit the content budget.
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    omitted: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    mime_type: Option<&'static str>,
+    /// Text content, `null` for binary and omitted files.
+    content: Option<&'a str>,
+}
+
+/// Generates a JSON snapshot of a project.
+///
+/// # Arguments
+///
+/// * `config` - The project configuration.
+/// * `files` - List of files with their paths and contents.
+/// * `languages` - Language definitions.
+///
+/// # Returns
+///
+/// * `Result<String>` - The generated JSON content or an error.
+pub fn generate_json(
+    config: &Config,
+    files: Vec<(PathBuf, FileContent)>,
+    languages: &Languages,
+) -> Result<String> {
+    let sorted_files = sort_files(files);
+    let snapshot = ProjectSnapshot {
+        project_name: &config.project_name,
+        tree: project_tree(config, &sorted_files)?,
+        files: file_records(config, &sorted_files, languages),
+    };
+
+    let mut json = serde_json::to_string_pretty(&snapshot)?;
+    json.push('\n');
+    Ok(json)
+}
+
+/// Generates a JSON Lines snapshot of a project.
+///
+/// The first line is a `project` record holding the project name and tree, followed by
+/// one `file` record per file.
+///
+/// # Arguments
+///
+/// * `config` - The project configuration.
+/// * `files` - List of files with their paths and contents.
+/// * `languages` - Language definitions.
+///
+/// # Returns
+///
+/// * `Result<String>` - The generated JSON Lines content or an error.
+pub fn generate_jsonl(
+    config: &Config,
+    files: Vec<(PathBuf, FileContent)>,
+    languages: &Languages,
+) -> Result<String> {
+    let sorted_files = sort_files(files);
+    let header = JsonlRecord::Project {
+        project_name: &config.project_name,
+        tree: project_tree(config, &sorted_files)?,
+    };
+
+    let mut jsonl = serde_json::to_string(&header)?;
+    jsonl.push('\n');
+    for record in file_records(config, &sorted_files, languages) {
+        jsonl.push_str(&serde_json::to_string(&JsonlRecord::File(record))?);
+        jsonl.push('\n');
+    }
+    Ok(jsonl)
+}
+
+/// Sorts files by path for consistent output.
+fn sort_files(files: Vec<(PathBuf, FileContent)>) -> Vec<(PathBuf, FileContent)> {
+    let mut sorted_files = files;
+    sorted_files.sort_by(|a, b| a.0.cmp(&b.0));
+    sorted_files
+}
+
+/// Renders the project tree as plain text, headed by the project name.
+fn project_tree(config: &Config, files: &[(PathBuf, FileContent)]) -> Result<String> {
+    Ok(format!(
+        "{}\n{}",
+        config.project_name,
+        tree_generator::generate_tree(&config.project_name, files, &config.project_path)?
+    ))
+}
+
+/// Builds the records describing each file.
+fn file_records<'a>(
+    config: &Config,
+    files: &'a [(PathBuf, FileContent)],
+    languages: &Languages,
+) -> Vec<FileRecord<'a>> {
+    files
+        .iter()
+        .map(|(file_path, content)| {
+            let relative_path = file_path.strip_prefix(&config.project_path).unwrap_or(file_path);
+            let path = relative_path.to_string_lossy().replace('\\', "/");
+            let language = language::detect_language(file_path, content.as_text(), languages).name;
+
+            match content {
+                FileContent::Text(text) => FileRecord {
+                    path,
+                    language,
+                    size: text.len() as u64,
+                    line_count: text.lines().count(),
+                    sha256: Some(binary_detector::sha256_hex(text.as_bytes())),
+                    binary: false,
+                    omitted: false,
+                    mime_type: None,
+                    content: Some(text),
+                },
+                FileContent::Binary(info) => FileRecord {
+                    path,
+                    language,
+                    size: info.size,
+                    line_count: 0,
+                    sha256: Some(info.sha256.clone()),
+                    binary: true,
+                    omitted: false,
+                    mime_type: Some(info.mime_type),
+                    content: None,
+                },
+                FileContent::Omitted { size } => FileRecord {
+                    path,
+                    language,
+                    size: *size,
+                    line_count: 0,
+                    sha256: None,
+                    binary: false,
+                    omitted: true,
+                    mime_type: None,
+                    content: None,
+                },
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::test_config;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_generate_jsonl() {
+        let config = test_config("output_file: demo.jsonl");
+        let files = vec![(
+            PathBuf::from("/project/src/main.rs"),
+            FileContent::Text("fn main() {}\n".to_string()),
+        )];
+        let languages = Languages::from(HashMap::from([("rs".to_string(), "Rust".to_string())]));
+
+        let jsonl = generate_jsonl(&config, files, &languages).unwrap();
+        let lines: Vec<serde_json::Value> = jsonl
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0]["type"], "project");
+        assert_eq!(lines[0]["project_name"], "Demo");
+        assert_eq!(lines[1]["type"], "file");
+        assert_eq!(lines[1]["path"], "src/main.rs");
+        assert_eq!(lines[1]["language"], "Rust");
+        assert_eq!(lines[1]["size"], 13);
+        assert_eq!(lines[1]["line_count"], 1);
+        assert_eq!(lines[1]["content"], "fn main() {}\n");
+    }
+}
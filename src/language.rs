@@ -1,9 +1,100 @@
 use serde::Deserialize;
 use std::collections::HashMap;
 use std::fs;
+use std::path::Path;
 use anyhow::{Context, Result};
 
-/// Loads language definitions from a YAML file.
+/// Built-in language definitions, in the format of `languages.yml`.
+const DEFAULT_LANGUAGES: &str = include_str!("default_languages.yml");
+
+/// Number of lines at the start and at the end of a file searched for a Vim modeline.
+const MODELINE_SEARCH_LINES: usize = 5;
+
+/// A language detected for a file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Language {
+    /// Display name used in headings and statistics (e.g., "C++").
+    pub name: String,
+    /// Identifier used in code fences (e.g., "cpp").
+    pub fence: String,
+}
+
+impl Language {
+    /// Creates a language from its display name, deriving the fence identifier.
+    fn from_name(name: &str) -> Self {
+        Language { name: name.to_string(), fence: fence_identifier(name) }
+    }
+
+    /// Returns the language of files whose language is unknown.
+    fn text() -> Self {
+        Language { name: "Text".to_string(), fence: "text".to_string() }
+    }
+}
+
+/// Comment markers of a language.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+pub struct CommentSyntax {
+    /// Markers starting a comment that ends with the line (e.g., "//").
+    #[serde(default)]
+    pub line: Vec<String>,
+    /// Start and end markers of block comments (e.g., "/*" and "*/").
+    #[serde(default)]
+    pub block: Vec<(String, String)>,
+}
+
+/// Language definitions used to detect the language of a file.
+#[derive(Debug, Clone, Default)]
+pub struct Languages {
+    /// Mapping of fence identifiers to display names.
+    names: HashMap<String, String>,
+    /// Mapping of lowercase file extensions to languages.
+    extensions: HashMap<String, Language>,
+    /// Mapping of exact file names (e.g., "Dockerfile") to languages.
+    filenames: HashMap<String, Language>,
+    /// Glob patterns with their languages, checked in order.
+    globs: Vec<(glob::Pattern, Language)>,
+    /// Mapping of interpreter names (e.g., "python3") to languages.
+    shebangs: HashMap<String, Language>,
+    /// Mapping of fence identifiers to comment syntax.
+    comments: HashMap<String, CommentSyntax>,
+}
+
+impl From<HashMap<String, String>> for Languages {
+    /// Creates language definitions from a mapping of file extensions to display names.
+    fn from(extensions: HashMap<String, String>) -> Self {
+        let extensions = extensions
+            .into_iter()
+            .map(|(ext, name)| (ext, Language::from_name(&name)))
+            .collect();
+        Languages { extensions, ..Languages::default() }
+    }
+}
+
+/// Returns the built-in language definitions.
+///
+/// They cover several hundred file extensions, common file names and interpreters,
+/// with display names (e.g., "C++") and code fence identifiers (e.g., "cpp").
+pub fn default_languages() -> Languages {
+    let config: LanguageConfig = serde_yaml::from_str(DEFAULT_LANGUAGES)
+        .expect("built-in language definitions are valid YAML");
+    let mut languages = Languages::default();
+    languages
+        .apply(config)
+        .expect("built-in language definitions are valid");
+    languages
+}
+
+/// Loads language definitions from a YAML file, on top of the built-in definitions.
+///
+/// The file may contain a `languages` mapping of file extensions, `filenames` (exact
+/// file names), `globs` (a list of `pattern` / `language` pairs), `shebangs`
+/// (interpreter names), `names` (display names by fence identifier) and `comments`
+/// (`line` and `block` comment markers by fence identifier). Its entries
+/// add to or override the built-in ones; an entry whose language is `~` (null)
+/// removes the built-in entry.
+///
+/// A language is either a fence identifier or display name (e.g., "cpp" or "C++"),
+/// resolved with `names`, or a mapping with both a `name` and a `fence`.
 ///
 /// # Arguments
 ///
@@ -11,18 +102,347 @@ use anyhow::{Context, Result};
 ///
 /// # Returns
 ///
-/// * `Result<HashMap<String, String>>` - A map of file extensions to language names.
-pub fn load_languages(languages_path: &std::path::Path) -> Result<HashMap<String, String>> {
+/// * `Result<Languages>` - The language definitions or an error.
+pub fn load_languages(languages_path: &Path) -> Result<Languages> {
     let content = fs::read_to_string(languages_path)
         .context(format!("Failed to read languages file: {}", languages_path.display()))?;
-    
-    let config: LanguageConfig = serde_yaml::from_str(&content)
+
+    // A file with comments only is an empty overlay
+    let config: Option<LanguageConfig> = serde_yaml::from_str(&content)
         .context(format!("Failed to parse languages file: {}", languages_path.display()))?;
-    
-    Ok(config.languages)
+
+    let mut languages = default_languages();
+    if let Some(config) = config {
+        languages
+            .apply(config)
+            .context(format!("Invalid languages file: {}", languages_path.display()))?;
+    }
+    Ok(languages)
+}
+
+/// Determines the language of a file.
+///
+/// In order, the language is taken from a Vim or Emacs modeline in the content, the
+/// exact file name, the first matching glob, the interpreter of a `#!` line, and
+/// finally the file extension.
+///
+/// # Arguments
+///
+/// * `file_path` - Path to the file.
+/// * `content` - Text content of the file, if available.
+/// * `languages` - Language definitions.
+///
+/// # Returns
+///
+/// * `Language` - The language, or "Text" if it cannot be determined.
+pub fn detect_language(file_path: &Path, content: Option<&str>, languages: &Languages) -> Language {
+    let file_name = file_path
+        .file_name()
+        .unwrap_or_default()
+        .to_string_lossy();
+
+    content
+        .and_then(modeline)
+        .and_then(|name| languages.language_for_name(&name))
+        .or_else(|| languages.filenames.get(file_name.as_ref()).cloned())
+        .or_else(|| {
+            languages
+                .globs
+                .iter()
+                .find(|(pattern, _)| pattern.matches(&file_name) || pattern.matches_path(file_path))
+                .map(|(_, language)| language.clone())
+        })
+        .or_else(|| content.and_then(interpreter).and_then(|name| languages.language_for_interpreter(&name)))
+        .or_else(|| {
+            let extension = file_path
+                .extension()
+                .unwrap_or_default()
+                .to_string_lossy()
+                .to_lowercase();
+            languages.extensions.get(&extension).cloned()
+        })
+        .unwrap_or_else(Language::text)
+}
+
+impl Languages {
+    /// Adds, overrides or removes the definitions of a languages file.
+    ///
+    /// Globs of the file are checked before the existing ones.
+    fn apply(&mut self, config: LanguageConfig) -> Result<()> {
+        // Names first, so the other entries can refer to them
+        merge(&mut self.names, config.names);
+        merge(&mut self.comments, config.comments);
+
+        let resolve = |value: Option<LanguageValue>| value.map(|value| self.resolve(value));
+        let extensions: Vec<_> = config.languages.into_iter().map(|(ext, value)| (ext.to_lowercase(), resolve(value))).collect();
+        let filenames: Vec<_> = config.filenames.into_iter().map(|(name, value)| (name, resolve(value))).collect();
+        let shebangs: Vec<_> = config.shebangs.into_iter().map(|(name, value)| (name, resolve(value))).collect();
+        let mut globs = Vec::new();
+        for glob in config.globs {
+            self.globs.retain(|(pattern, _)| pattern.as_str() != glob.pattern);
+            if let Some(value) = glob.language {
+                let pattern = glob::Pattern::new(&glob.pattern)
+                    .context(format!("Invalid language glob pattern: {}", glob.pattern))?;
+                globs.push((pattern, self.resolve(value)));
+            }
+        }
+        merge(&mut self.extensions, extensions);
+        merge(&mut self.filenames, filenames);
+        merge(&mut self.shebangs, shebangs);
+
+        globs.append(&mut self.globs);
+        self.globs = globs;
+        Ok(())
+    }
+
+    /// Returns the comment syntax of a language, if known.
+    pub fn comment_syntax(&self, language: &Language) -> Option<&CommentSyntax> {
+        self.comments.get(&language.fence)
+    }
+
+    /// Resolves an interpreter name, ignoring a version suffix (e.g., "python3.11").
+    fn language_for_interpreter(&self, interpreter: &str) -> Option<Language> {
+        self.shebangs.get(interpreter).cloned().or_else(|| {
+            let unversioned = interpreter.trim_end_matches(|c: char| c.is_ascii_digit() || c == '.');
+            self.shebangs.get(unversioned).cloned()
+        })
+    }
+
+    /// Resolves a language named by a modeline, as an interpreter, an extension, a
+    /// fence identifier or a display name.
+    fn language_for_name(&self, name: &str) -> Option<Language> {
+        let name = name.to_lowercase();
+        self.language_for_interpreter(&name)
+            .or_else(|| self.extensions.get(&name).cloned())
+            .or_else(|| self.known_language(&name))
+    }
+
+    /// Finds a language of `names` by fence identifier or display name, ignoring case.
+    fn known_language(&self, name: &str) -> Option<Language> {
+        let name = name.to_lowercase();
+        if let Some(display_name) = self.names.get(&name) {
+            return Some(Language { name: display_name.clone(), fence: name });
+        }
+        // Several identifiers may share a display name; pick one deterministically
+        self.names
+            .iter()
+            .filter(|(_, display_name)| display_name.to_lowercase() == name)
+            .min()
+            .map(|(fence, display_name)| Language { name: display_name.clone(), fence: fence.clone() })
+    }
+
+    /// Resolves a language of a languages file.
+    ///
+    /// A single string is looked up in `names`, so both "cpp" and "C++" (the format of
+    /// older languages files) resolve to the same language; unknown strings are taken
+    /// as a display name.
+    fn resolve(&self, value: LanguageValue) -> Language {
+        match value {
+            LanguageValue::Name(name) => self.known_language(&name).unwrap_or_else(|| Language::from_name(&name)),
+            LanguageValue::Definition { name, fence } => Language { name, fence },
+        }
+    }
+}
+
+/// Inserts entries with a value and removes entries without one.
+fn merge<T>(map: &mut HashMap<String, T>, entries: impl IntoIterator<Item = (String, Option<T>)>) {
+    for (key, language) in entries {
+        match language {
+            Some(language) => map.insert(key, language),
+            None => map.remove(&key),
+        };
+    }
+}
+
+/// Derives a code fence identifier from a display name (e.g., "C++" becomes "cpp").
+fn fence_identifier(name: &str) -> String {
+    name.trim()
+        .to_lowercase()
+        .replace("++", "pp")
+        .replace('#', "sharp")
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join("-")
+}
+
+/// Returns the interpreter named by the `#!` line of a script (e.g., "python3").
+///
+/// `/usr/bin/env` is skipped, together with its options.
+fn interpreter(content: &str) -> Option<String> {
+    let shebang = content.lines().next()?.strip_prefix("#!")?;
+    let mut words = shebang.split_whitespace();
+    let mut program = words.next()?.rsplit('/').next()?;
+    if program == "env" {
+        program = words.find(|word| !word.starts_with('-') && !word.contains('='))?;
+    }
+    Some(program.to_string())
+}
+
+/// Returns the file type set by a modeline, if any.
+///
+/// Emacs modelines (`-*- mode: python -*-` or `-*- python -*-`) are read from the first
+/// two lines, and Vim modelines (`vim: set ft=python:` or `vi: filetype=python`) from the
+/// first and last lines. As in Vim, the modeline marker must start the line or follow
+/// whitespace, so that `index: ft=c` is not a modeline.
+fn modeline(content: &str) -> Option<String> {
+    let lines: Vec<&str> = content.lines().collect();
+
+    let emacs = lines.iter().take(2).find_map(|line| {
+        let start = line.find("-*-")? + 3;
+        let end = start + line[start..].find("-*-")?;
+        let variables = line[start..end].trim();
+        if !variables.contains(':') {
+            return Some(variables.to_string());
+        }
+        variables.split(';').find_map(|variable| {
+            let (key, value) = variable.split_once(':')?;
+            key.trim().eq_ignore_ascii_case("mode").then(|| value.trim().to_string())
+        })
+    });
+
+    let tail_start = lines.len().saturating_sub(MODELINE_SEARCH_LINES).max(MODELINE_SEARCH_LINES);
+    let candidates = lines.iter().take(MODELINE_SEARCH_LINES).chain(lines.iter().skip(tail_start));
+    let vim = || {
+        candidates.clone().find_map(|line| {
+            let start = ["vim:", "vi:", "ex:"]
+                .iter()
+                .flat_map(|marker| line.match_indices(marker))
+                .filter(|(i, _)| line[..*i].chars().next_back().is_none_or(char::is_whitespace))
+                .map(|(i, marker)| i + marker.len())
+                .min()?;
+            line[start..]
+                .split(|c: char| c.is_whitespace() || c == ':')
+                .find_map(|option| {
+                    let (key, value) = option.split_once('=')?;
+                    matches!(key, "ft" | "filetype" | "syntax" | "syn").then(|| value.to_string())
+                })
+        })
+    };
+
+    emacs.filter(|mode| !mode.is_empty()).or_else(vim)
 }
 
+/// Content of a languages file. A `None` value removes an entry.
 #[derive(Deserialize)]
 struct LanguageConfig {
-    languages: HashMap<String, String>,
-}
\ No newline at end of file
+    #[serde(default)]
+    names: HashMap<String, Option<String>>,
+    #[serde(default)]
+    languages: HashMap<String, Option<LanguageValue>>,
+    #[serde(default)]
+    filenames: HashMap<String, Option<LanguageValue>>,
+    #[serde(default)]
+    globs: Vec<GlobLanguage>,
+    #[serde(default)]
+    shebangs: HashMap<String, Option<LanguageValue>>,
+    #[serde(default)]
+    comments: HashMap<String, Option<CommentSyntax>>,
+}
+
+/// A language as written in a languages file.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum LanguageValue {
+    /// A fence identifier or display name, resolved with `names`.
+    Name(String),
+    /// A language with an explicit display name and fence identifier.
+    Definition { name: String, fence: String },
+}
+
+/// A language applied to the files matching a glob pattern.
+#[derive(Deserialize)]
+struct GlobLanguage {
+    /// Glob pattern matched against the file name or path (e.g., "Dockerfile.*").
+    pattern: String,
+    /// Language, or `None` to remove a glob with the same pattern.
+    language: Option<LanguageValue>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect_language() {
+        let language = |name: &str| Language::from_name(name);
+        let languages = Languages {
+            names: HashMap::new(),
+            extensions: HashMap::from([
+                ("py".to_string(), language("Python")),
+                ("sh".to_string(), language("Shell")),
+                ("txt".to_string(), language("Text")),
+            ]),
+            filenames: HashMap::from([("CMakeLists.txt".to_string(), language("CMake"))]),
+            globs: vec![(glob::Pattern::new("Dockerfile*").unwrap(), language("Dockerfile"))],
+            shebangs: HashMap::from([
+                ("python".to_string(), language("Python")),
+                ("bash".to_string(), language("Shell")),
+            ]),
+            comments: HashMap::new(),
+        };
+        let detect = |path: &str, content: Option<&str>| detect_language(Path::new(path), content, &languages).name;
+
+        assert_eq!(detect("src/main.py", None), "Python");
+        assert_eq!(detect("CMakeLists.txt", None), "CMake");
+        assert_eq!(detect("docker/Dockerfile.dev", None), "Dockerfile");
+        assert_eq!(detect("bin/tool", Some("#!/usr/bin/env -S python3.11 -u\nprint()")), "Python");
+        assert_eq!(detect("bin/run", Some("#!/bin/bash\necho")), "Shell");
+        assert_eq!(detect("build", Some("# -*- mode: python; coding: utf-8 -*-\n")), "Python");
+        assert_eq!(detect("notes.txt", Some("x\n\n# vim: set ft=sh ts=4:\n")), "Shell");
+        assert_eq!(detect("bin/tool", Some("vi:ft=python\n")), "Python");
+        // Modeline markers must start the line or follow whitespace
+        assert_eq!(detect("notes.txt", Some("index: ft=python\nregex: syntax=sh\nhex: ft=sh\n")), "Text");
+        assert_eq!(detect("notes.txt", Some("regex: ft=sh vim: ft=python\n")), "Python");
+        assert_eq!(detect("bin/unknown", Some("#!/usr/bin/perl\n")), "Text");
+    }
+
+    #[test]
+    fn test_load_languages_overlay() {
+        let temp = tempfile::tempdir().unwrap();
+        let languages_path = temp.path().join("languages.yml");
+        fs::write(
+            &languages_path,
+            "languages:\n  RS: rs\n  foo: foo-lang\n  toml: ~\nglobs:\n  - pattern: \"Dockerfile.*\"\n    language: ~\n",
+        ).unwrap();
+        let defaults = default_languages();
+        let languages = load_languages(&languages_path).unwrap();
+        let detect = |languages: &Languages, path: &str| detect_language(Path::new(path), None, languages).fence;
+
+        assert!(defaults.extensions.len() > 300);
+        assert_eq!(detect(&defaults, "src/main.rs"), "rust");
+        assert_eq!(detect(&defaults, "Cargo.toml"), "toml");
+        assert_eq!(detect(&defaults, "Dockerfile.dev"), "dockerfile");
+        assert_eq!(detect(&languages, "src/main.rs"), "rs");
+        assert_eq!(detect(&languages, "a.foo"), "foo-lang");
+        assert_eq!(detect(&languages, "Cargo.toml"), "text");
+        assert_eq!(detect(&languages, "Dockerfile.dev"), "text");
+        assert_eq!(detect(&languages, "Makefile"), "makefile");
+
+        fs::write(&languages_path, "# comments only\n").unwrap();
+        assert_eq!(load_languages(&languages_path).unwrap().extensions.len(), defaults.extensions.len());
+    }
+
+    #[test]
+    fn test_load_languages_names() {
+        let temp = tempfile::tempdir().unwrap();
+        let languages_path = temp.path().join("languages.yml");
+        // Display names, as in older languages files
+        fs::write(
+            &languages_path,
+            "languages:\n  rs: \"Rust\"\n  cpp: \"C++\"\n  foo: \"Foo Lang\"\n  bar: {name: Bar, fence: bar-x}\n",
+        ).unwrap();
+        let defaults = default_languages();
+        let languages = load_languages(&languages_path).unwrap();
+        let detect = |languages: &Languages, path: &str| detect_language(Path::new(path), None, languages);
+        let language = |name: &str, fence: &str| Language { name: name.to_string(), fence: fence.to_string() };
+
+        assert_eq!(detect(&defaults, "src/main.rs"), language("Rust", "rust"));
+        assert_eq!(detect(&defaults, "src/main.cs"), language("C#", "csharp"));
+        assert_eq!(detect(&defaults, "unknown.xyz"), language("Text", "text"));
+        assert_eq!(detect(&languages, "src/main.rs"), language("Rust", "rust"));
+        assert_eq!(detect(&languages, "src/main.cpp"), language("C++", "cpp"));
+        assert_eq!(detect(&languages, "a.foo"), language("Foo Lang", "foo-lang"));
+        assert_eq!(detect(&languages, "a.bar"), language("Bar", "bar-x"));
+        assert_eq!(fence_identifier("F#"), "fsharp");
+    }
+}
@@ -5,14 +5,25 @@ use anyhow::{Context, Result};
 ///
 /// The logging configuration is loaded from `log4rs.yml`.
 ///
+/// # Arguments
+///
+/// * `to_stderr` - Whether console appenders write to stderr instead of their
+///   configured target, to keep stdout for the generated documentation.
+/// * `verbose` - Whether to log at least at the debug level, whatever the level
+///   configured for the root logger.
+///
 /// # Returns
 ///
 /// * `Result<()>` - Success or error.
-pub fn init_logger() -> Result<()> {
+pub fn init_logger(to_stderr: bool, verbose: bool) -> Result<()> {
     // Ensure the logs directory exists
     fs::create_dir_all("logs")
         .context("Failed to create logs directory")?;
 
+    if to_stderr || verbose {
+        return init_adjusted_logger(to_stderr, verbose);
+    }
+
     // Load the log4rs configuration from the YAML file
     let config = log4rs::config::load_config_file("log4rs.yml", Default::default())
         .context("Failed to load log4rs configuration")?;
@@ -23,4 +34,104 @@ pub fn init_logger() -> Result<()> {
 
     log::info!("Logger initialized with log4rs configuration.");
     Ok(())
-}
\ No newline at end of file
+}
+
+/// Initializes the logger configured by `log4rs.yml`, with every console appender
+/// writing to stderr and the root logger at the debug level if requested.
+///
+/// The configuration is not watched for changes in this mode.
+fn init_adjusted_logger(to_stderr: bool, verbose: bool) -> Result<()> {
+    let content = fs::read_to_string("log4rs.yml")
+        .context("Failed to read log4rs configuration")?;
+    let config = adjusted_config(&content, to_stderr, verbose)?;
+    log4rs::config::init_raw_config(config)
+        .context("Failed to initialize log4rs")?;
+
+    log::info!("Logger initialized with log4rs configuration{}.", if to_stderr { ", writing to stderr" } else { "" });
+    Ok(())
+}
+
+/// Parses a log4rs configuration, adjusting it for the command line options.
+///
+/// # Arguments
+///
+/// * `content` - The YAML log4rs configuration.
+/// * `to_stderr` - Whether to set the target of every console appender to stderr.
+/// * `verbose` - Whether to raise the level of the root logger to debug.
+///
+/// # Returns
+///
+/// * `Result<RawConfig>` - The modified configuration or an error.
+fn adjusted_config(content: &str, to_stderr: bool, verbose: bool) -> Result<log4rs::config::RawConfig> {
+    let mut config: serde_yaml::Value = serde_yaml::from_str(content)
+        .context("Failed to load log4rs configuration")?;
+    if to_stderr {
+        redirect_console_appenders(&mut config);
+    }
+    if verbose {
+        raise_root_level(&mut config);
+    }
+    serde_yaml::from_value(config).context("Failed to load log4rs configuration")
+}
+
+/// Sets the target of every console appender of a log4rs configuration to stderr.
+fn redirect_console_appenders(config: &mut serde_yaml::Value) {
+    if let Some(appenders) = config.get_mut("appenders").and_then(serde_yaml::Value::as_mapping_mut) {
+        for appender in appenders.values_mut() {
+            if appender.get("kind").and_then(serde_yaml::Value::as_str) == Some("console") {
+                appender["target"] = "stderr".into();
+            }
+        }
+    }
+}
+
+/// Sets the level of the root logger of a log4rs configuration to debug, unless it
+/// already logs at the trace level.
+fn raise_root_level(config: &mut serde_yaml::Value) {
+    let Some(root) = config.get_mut("root").and_then(serde_yaml::Value::as_mapping_mut) else { return };
+    let level = root.get("level").and_then(serde_yaml::Value::as_str);
+    if !level.is_some_and(|level| level.eq_ignore_ascii_case("trace")) {
+        root.insert("level".into(), "debug".into());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_redirect_console_appenders() {
+        let mut config: serde_yaml::Value = serde_yaml::from_str("\
+appenders:
+  stdout:
+    kind: console
+  file:
+    kind: file
+    path: logs/test.log
+root:
+  level: info
+  appenders: [stdout, file]
+").unwrap();
+        redirect_console_appenders(&mut config);
+        assert_eq!(config["appenders"]["stdout"]["target"], "stderr");
+        assert!(config["appenders"]["file"].get("target").is_none());
+
+        // The repository configuration stays valid once redirected
+        let content = fs::read_to_string("log4rs.yml").unwrap();
+        let (appenders, errors) = adjusted_config(&content, true, true).unwrap().appenders_lossy(&Default::default());
+        assert!(errors.is_empty());
+        assert_eq!(appenders.len(), 2);
+    }
+
+    #[test]
+    fn test_raise_root_level() {
+        let level = |yaml: &str| {
+            let mut config: serde_yaml::Value = serde_yaml::from_str(yaml).unwrap();
+            raise_root_level(&mut config);
+            config["root"]["level"].as_str().map(str::to_string)
+        };
+        assert_eq!(level("root:\n  level: warn\n").as_deref(), Some("debug"));
+        assert_eq!(level("root:\n  appenders: [stdout]\n").as_deref(), Some("debug"));
+        assert_eq!(level("root:\n  level: trace\n").as_deref(), Some("trace"));
+    }
+}
@@ -1,141 +1,459 @@
+mod binary_detector;
+mod commands;
 mod config;
+mod destination;
+mod diff_generator;
+mod encoding_detector;
 mod file_processor;
+mod git_repository;
+mod html_generator;
+mod json_generator;
 mod markdown_generator;
+mod part_splitter;
+mod project_options;
+mod report;
+mod statistics;
 mod logger;
 mod language;
+mod token_counter;
 mod tree_generator;
+mod xml_generator;
 
 use std::fs;
 use std::path::{Path, PathBuf};
+use std::time::Instant;
 use anyhow::{Context, Result};
-use clap::Parser;
+use clap::{Parser, Subcommand};
 use indicatif::{ProgressBar, ProgressStyle};
+use config::OutputFormat;
+use destination::Destination;
+use part_splitter::PartBudget;
+use project_options::ProjectOptions;
+use report::{ProjectReport, ProjectStatus, SummaryFormat};
+
+/// Languages file read when `--languages-file` is not given, if it exists.
+const DEFAULT_LANGUAGES_FILE: &str = "languages.yml";
 
 #[derive(Parser, Debug)]
 #[command(author, version, about, long_about = None)]
 struct Args {
     /// Path to the projects directory
-    #[arg(short, long, default_value = "projects")]
+    #[arg(short, long, default_value = "projects", global = true)]
     projects_dir: PathBuf,
 
-    /// Path to the languages definition file
-    #[arg(short, long, default_value = "languages.yml")]
-    languages_file: PathBuf,
+    /// Path to a project configuration file, which may be outside the projects
+    /// directory; may be repeated
+    #[arg(short, long = "config", value_name = "CONFIG", global = true)]
+    configs: Vec<PathBuf>,
+
+    /// Path to a languages definition file extending the built-in definitions
+    /// [default: languages.yml, if it exists]
+    #[arg(short, long, global = true)]
+    languages_file: Option<PathBuf>,
 
     /// Output directory for generated documentation
-    #[arg(short, long, default_value = "output")]
+    #[arg(long, default_value = "output", global = true)]
     output_dir: PathBuf,
 
-    /// Enable verbose logging
-    #[arg(short, long)]
+    /// Enable verbose logging, at the debug level whatever the level set in log4rs.yml
+    #[arg(short, long, global = true)]
     verbose: bool,
+
+    /// Command to run [default: generate]
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Subcommand, Debug, Clone)]
+enum Command {
+    /// Generate documentation for all projects, or for the selected ones
+    Generate {
+        /// Projects to generate: configuration file names, project names, glob patterns
+        /// or project directories configured by the options below
+        projects: Vec<String>,
+
+        /// Write the documentation to stdout instead of the output files, with logs on
+        /// stderr and no progress bar; `-o -` does the same for a project directory
+        #[arg(long)]
+        stdout: bool,
+
+        /// Line written to stdout before each document, which allows several projects
+        /// or parts; `{project}` and `{file}` are replaced by the project name and the
+        /// output file name (e.g., '==> {file} <==')
+        #[arg(long, value_name = "FORMAT")]
+        separator: Option<String>,
+
+        /// Keep generating the other projects when one fails, then print a summary and
+        /// exit with an error if any failed
+        #[arg(short, long)]
+        keep_going: bool,
+
+        /// Print a summary of the generated projects [default: table with --keep-going]
+        #[arg(long, value_name = "FORMAT")]
+        summary: Option<SummaryFormat>,
+
+        #[command(flatten)]
+        options: ProjectOptions,
+    },
+    /// List the project configurations with their resolved paths
+    List {
+        /// Projects to list: configuration file names, project names or glob patterns
+        projects: Vec<String>,
+    },
+    /// Check the project configurations and report all problems
+    Validate {
+        /// Projects to check: configuration file names, project names or glob patterns
+        projects: Vec<String>,
+    },
+    /// Create a project configuration for a directory
+    Init {
+        /// Directory of the project
+        directory: PathBuf,
+
+        /// Name of the project [default: the directory name]
+        #[arg(long)]
+        name: Option<String>,
+
+        /// Overwrite an existing configuration
+        #[arg(short, long)]
+        force: bool,
+    },
+    /// Show the tree and files a project would document, without reading the files
+    Preview {
+        /// Projects to preview: configuration file names, project names, glob patterns
+        /// or project directories configured by the options below
+        projects: Vec<String>,
+
+        #[command(flatten)]
+        options: ProjectOptions,
+    },
 }
 
 fn main() -> Result<()> {
     let args = Args::parse();
 
-    // Initialize the logger with the configuration from log4rs.yml
-    logger::init_logger().context("Failed to initialize logger")?;
+    let command = args.command.clone().unwrap_or(Command::Generate {
+        projects: Vec::new(),
+        stdout: false,
+        separator: None,
+        keep_going: false,
+        summary: None,
+        options: ProjectOptions::default(),
+    });
+    let destination = match &command {
+        Command::Generate { stdout, separator, options, .. } => Destination::from_args(*stdout, separator.clone(), options)?,
+        _ => Destination::Files,
+    };
 
+    // Initialize the logger with the configuration from log4rs.yml, keeping stdout for
+    // the results of commands meant to be scripted and for documentation written there
+    let logs_to_stderr = !matches!(command, Command::Generate { .. }) || destination != Destination::Files;
+    logger::init_logger(logs_to_stderr, args.verbose).context("Failed to initialize logger")?;
+
+    let select = |projects: &[String]| commands::select_config_files(&args.projects_dir, projects, &args.configs);
+    let select_projects = |projects: &[String], options: &ProjectOptions| {
+        commands::select_projects(&args.projects_dir, projects, &args.configs, options)
+    };
+    match command {
+        Command::Generate { projects, options, keep_going, summary, .. } => {
+            let summary = summary.or(keep_going.then_some(SummaryFormat::Table));
+            generate(&args, &select_projects(&projects, &options)?, &options, &destination, keep_going, summary)
+        }
+        Command::List { projects } => commands::list(&select(&projects)?, &args.output_dir),
+        Command::Validate { projects } => commands::validate(&select(&projects)?),
+        Command::Init { directory, name, force } => {
+            let config_path = commands::init(&directory, name.as_deref(), &args.projects_dir, force)?;
+            println!("Created {}", config_path.display());
+            Ok(())
+        }
+        Command::Preview { projects, options } => commands::preview(&select_projects(&projects, &options)?, &options),
+    }
+}
+
+/// Generates the documentation of the selected projects.
+///
+/// # Arguments
+///
+/// * `args` - Command line arguments.
+/// * `projects` - The selected projects.
+/// * `options` - Project options given on the command line, for directories.
+/// * `destination` - Where the documents are written.
+/// * `keep_going` - Whether the other projects are generated after a failure.
+/// * `summary` - Format of the summary printed at the end, if any.
+///
+/// # Returns
+///
+/// * `Result<()>` - Success, or the error of the first failed project (the number of
+///   failed projects with `keep_going`).
+fn generate(
+    args: &Args,
+    projects: &[commands::Project],
+    options: &ProjectOptions,
+    destination: &Destination,
+    keep_going: bool,
+    summary: Option<SummaryFormat>,
+) -> Result<()> {
     log::info!("Starting to generate project documentation...");
 
-    // Load common language definitions from the YAML file
-    let languages = language::load_languages(&args.languages_file)
-        .context("Failed to load language definitions")?;
-    log::info!("Loaded language definitions from {}", args.languages_file.display());
+    // Load common language definitions, extending the built-in ones with the YAML file
+    let languages_file = args.languages_file
+        .clone()
+        .or_else(|| Some(PathBuf::from(DEFAULT_LANGUAGES_FILE)).filter(|path| path.exists()));
+    let languages = match &languages_file {
+        Some(languages_file) => {
+            let languages = language::load_languages(languages_file)
+                .context("Failed to load language definitions")?;
+            log::info!("Loaded language definitions from {}", languages_file.display());
+            languages
+        }
+        None => {
+            log::info!("Using built-in language definitions");
+            language::default_languages()
+        }
+    };
 
     // Ensure output directory exists
-    fs::create_dir_all(&args.output_dir)
-        .context("Failed to create output directory")?;
+    if *destination == Destination::Files {
+        fs::create_dir_all(&args.output_dir)
+            .context("Failed to create output directory")?;
+    }
 
-    // Get list of project configuration files
-    let config_files = get_config_files(&args.projects_dir)?;
-    let total_projects = config_files.len();
+    let total_projects = projects.len();
 
     if total_projects == 0 {
         log::warn!("No project configuration files found in {}", args.projects_dir.display());
         return Ok(());
     }
+    if !destination.allows(total_projects) {
+        anyhow::bail!("{} projects are selected; choose a --separator to write them all to stdout", total_projects);
+    }
 
-    // Create progress bar
-    let pb = ProgressBar::new(total_projects as u64);
+    // Create progress bar, hidden when the documentation is written to stdout
+    let pb = match destination {
+        Destination::Files => ProgressBar::new(total_projects as u64),
+        Destination::Stdout { .. } => ProgressBar::hidden(),
+    };
     pb.set_style(ProgressStyle::default_bar()
         .template("{spinner:.green} [{elapsed_precise}] [{bar:40.cyan/blue}] {pos}/{len} ({eta})")
         .unwrap()
         .progress_chars("#>-"));
 
-    // Process each project configuration
-    for config_path in config_files {
-        let config_name = config_path.file_stem()
-            .unwrap_or_default()
-            .to_string_lossy();
-        
-        pb.set_message(format!("Processing: {}", config_name));
-
-        // Load the project configuration from the YAML file
-        let config = config::Config::load(&config_path)
-            .context(format!("Failed to load configuration: {}", config_path.display()))?;
-        log::info!("Loaded project configuration: {}", config.project_name);
-
-        // Get the project root directory
-        let project_root = Path::new(&config.project_path);
-
-        // Process files and directories specified in the configuration
-        let files = file_processor::process_files(
-            &config.project_path,
-            &config.files,
-            &config.directories,
-            &config.exclude_directories,
-            &config.exclude_patterns,
-            config.max_file_size,
-        ).context(format!("Failed to process files for project: {}", config.project_name))?;
-        log::info!("Processed {} files for project: {}", files.len(), config.project_name);
-
-        // Generate Markdown content for the project documentation
-        let markdown_content = markdown_generator::generate_markdown(
-            &config.project_name,
-            files,
-            &languages,
-            project_root,
-            &config.markdown_lang,
-        ).context(format!("Failed to generate markdown for project: {}", config.project_name))?;
-
-        // Write the generated Markdown content to the output file with UTF-8 encoding
-        let output_path = args.output_dir.join(&config.output_file);
-        use std::io::Write;
-        let mut file = fs::File::create(&output_path)
-            .context(format!("Failed to create output file: {}", output_path.display()))?;
-        file.write_all(markdown_content.as_bytes())
-            .context(format!("Failed to write output file: {}", output_path.display()))?;
-        log::info!("Generated documentation for project: {} -> {}", config.project_name, output_path.display());
+    // Process each project, recording how it went
+    let mut reports = Vec::new();
+    let mut first_error = None;
+    for project in projects {
+        pb.set_message(format!("Processing: {}", project.name()));
+
+        let started = Instant::now();
+        let result = generate_project(args, project, options, &languages, destination);
+        let (files, bytes, status) = match result {
+            Ok((files, bytes)) => (files, bytes, ProjectStatus::Ok),
+            Err(error) => {
+                let status = ProjectStatus::Failed(format!("{:#}", error));
+                if keep_going {
+                    log::error!("Failed to generate project {}: {:#}", project.name(), error);
+                } else {
+                    first_error = Some(error);
+                }
+                (0, 0, status)
+            }
+        };
+        reports.push(ProjectReport { project: project.name(), files, bytes, duration: started.elapsed(), status });
 
         pb.inc(1);
+        if first_error.is_some() {
+            break;
+        }
     }
 
     pb.finish_with_message("Done");
+
+    // Print the summary out of the way of documentation written to stdout
+    if let Some(format) = summary {
+        let summary = report::render_summary(&reports, format)?;
+        match destination {
+            Destination::Files => print!("{}", summary),
+            Destination::Stdout { .. } => eprint!("{}", summary),
+        }
+    }
+
+    if let Some(error) = first_error {
+        return Err(error);
+    }
+    let failed = report::failures(&reports);
+    if failed > 0 {
+        anyhow::bail!("{} of {} projects failed", failed, reports.len());
+    }
     log::info!("Project documentation generation complete.");
     Ok(())
 }
 
-/// Get all YAML configuration files from the projects directory
-fn get_config_files(projects_dir: &Path) -> Result<Vec<PathBuf>> {
-    let mut config_files = Vec::new();
-    
-    if !projects_dir.exists() {
-        return Ok(config_files);
+/// Generates the documentation of a project.
+///
+/// # Arguments
+///
+/// * `args` - Command line arguments.
+/// * `project` - The project to generate.
+/// * `options` - Project options given on the command line, for directories.
+/// * `languages` - Language definitions.
+/// * `destination` - Where the documents are written.
+///
+/// # Returns
+///
+/// * `Result<(usize, u64)>` - The number of files documented and of bytes written, or
+///   an error.
+fn generate_project(
+    args: &Args,
+    project: &commands::Project,
+    options: &ProjectOptions,
+    languages: &language::Languages,
+    destination: &Destination,
+) -> Result<(usize, u64)> {
+    // Load the project configuration from the YAML file or the command line options
+    let config = project.load(options)?;
+    log::info!("Loaded project configuration: {}", config.project_name);
+
+    // Document the changes between two revisions instead of the files themselves
+    if let Some(range) = &config.diff {
+        let output_path = args.output_dir.join(&config.output_file);
+        let (changed_files, document) = generate_diff_document(&config, range, languages)
+            .context(format!("Failed to generate diff document for project: {}", config.project_name))?;
+        destination.write(&config.project_name, &output_path, &document)?;
+        log::info!("Generated diff document for project: {} -> {}", config.project_name, destination.describe(&output_path));
+
+        return Ok((changed_files, document.len() as u64));
+    }
+
+    // Process files and directories specified in the configuration
+    let files = file_processor::process_files(&config)
+        .context(format!("Failed to process files for project: {}", config.project_name))?;
+    log::info!("Processed {} files for project: {}", files.len(), config.project_name);
+
+    // Count tokens per file to size the documentation for model context windows
+    let token_counter = token_counter::TokenCounter::new(config.token_encoding);
+    let mut token_stats = token_counter.count_files(&files);
+
+    // Keep the highest-priority files within the global content budget
+    let files = file_processor::apply_budget(files, &config, &token_stats);
+    token_stats.exclude_omitted(&files);
+    for (file_path, tokens) in &token_stats.files {
+        log::debug!("{} tokens: {}", tokens, file_path.display());
     }
+    log::info!("Counted {} tokens ({}) in files of project: {}",
+        token_stats.total, config.token_encoding.name(), config.project_name);
 
-    for entry in fs::read_dir(projects_dir)
-        .context(format!("Failed to read projects directory: {}", projects_dir.display()))? {
-        let entry = entry.context("Failed to read directory entry")?;
-        let config_path = entry.path();
-        
-        if config_path.is_file() && config_path.extension().unwrap_or_default() == "yml" {
-            config_files.push(config_path);
+    let documented_files = files
+        .iter()
+        .filter(|(_, content)| !matches!(content, file_processor::FileContent::Omitted { .. }))
+        .count();
+
+    // Read the repository revision to record in the documentation
+    let git_metadata = if config.git_metadata {
+        let file_paths = files.iter().map(|(file_path, _)| file_path);
+        let metadata = git_repository::read_metadata(&config.project_path, file_paths)
+            .context(format!("Failed to read git metadata for project: {}", config.project_name))?;
+        Some(metadata)
+    } else {
+        None
+    };
+
+    // Generate the project documentation in the configured format
+    let output_path = args.output_dir.join(&config.output_file);
+    let single = |document: String| vec![(output_path.clone(), document)];
+    let documents = match config.output_format() {
+        OutputFormat::Markdown => generate_markdown_documents(&config, files, languages, &token_stats, git_metadata.as_ref(), &token_counter, &output_path),
+        OutputFormat::Html => {
+            let summary = config.token_summary.then_some(&token_stats);
+            html_generator::generate_html(&config, files, languages, summary, git_metadata.as_ref()).map(single)
         }
+        OutputFormat::Json => json_generator::generate_json(&config, files, languages).map(single),
+        OutputFormat::Jsonl => json_generator::generate_jsonl(&config, files, languages).map(single),
+        OutputFormat::Xml => xml_generator::generate_xml(&config, files, languages).map(single),
+    }.context(format!("Failed to generate documentation for project: {}", config.project_name))?;
+
+    // Write the generated documentation to the output files with UTF-8 encoding
+    if !destination.allows(documents.len()) {
+        anyhow::bail!("Project {} is split into {} documents; choose a --separator to write them all to stdout",
+            config.project_name, documents.len());
     }
+    for (path, document) in &documents {
+        // Counting a whole document tokenizes it again, so it is only done when debugging
+        log::debug!("Generated document {} has {} tokens ({})",
+            path.display(), token_counter.count(document), config.token_encoding.name());
+        destination.write(&config.project_name, path, document)?;
+    }
+    log::info!("Generated documentation for project: {} -> {}", config.project_name, destination.describe(&output_path));
+
+    let bytes = documents.iter().map(|(_, document)| document.len() as u64).sum();
+    Ok((documented_files, bytes))
+}
 
-    // Sort for consistent ordering
-    config_files.sort();
-    Ok(config_files)
+/// Generates the Markdown documentation of a project, split into parts if configured.
+///
+/// # Returns
+///
+/// * `Result<Vec<(PathBuf, String)>>` - The output paths and contents of the generated
+///   documents: the parts (if any) followed by the main output file.
+fn generate_markdown_documents(
+    config: &config::Config,
+    files: Vec<(PathBuf, file_processor::FileContent)>,
+    languages: &language::Languages,
+    token_stats: &token_counter::TokenStats,
+    git_metadata: Option<&git_repository::GitMetadata>,
+    token_counter: &token_counter::TokenCounter,
+    output_path: &Path,
+) -> Result<Vec<(PathBuf, String)>> {
+    let summary = config.token_summary.then_some(token_stats);
+    let Some(budget) = PartBudget::from_config(config, token_counter) else {
+        let document = markdown_generator::generate_markdown(config, files, languages, summary, git_metadata)?;
+        return Ok(vec![(output_path.to_path_buf(), document)]);
+    };
+
+    let split = markdown_generator::generate_markdown_parts(
+        config,
+        files,
+        languages,
+        summary,
+        git_metadata,
+        &budget,
+        &|number| part_file_name(&config.output_file, number),
+    )?;
+    let mut documents: Vec<(PathBuf, String)> = split.parts
+        .into_iter()
+        .enumerate()
+        .map(|(index, part)| (output_path.with_file_name(part_file_name(&config.output_file, index + 1)), part))
+        .collect();
+    documents.push((output_path.to_path_buf(), split.index));
+    Ok(documents)
+}
+
+/// Generates the Markdown document of the changes of a project between two revisions.
+///
+/// Only changed files within the configured `files` and `directories` are included.
+///
+/// # Returns
+///
+/// * `Result<(usize, String)>` - The number of changed files and the document.
+fn generate_diff_document(
+    config: &config::Config,
+    range: &config::DiffRange,
+    languages: &language::Languages,
+) -> Result<(usize, String)> {
+    let files: Vec<_> = git_repository::diff_revisions(&config.project_path, &range.from, &range.to)?
+        .into_iter()
+        .filter(|(file_path, _)| file_processor::is_in_scope(file_path, config))
+        .collect();
+    log::info!("Documenting {} changed files for project: {}", files.len(), config.project_name);
+    let changed_files = files.len();
+    let document = diff_generator::generate_diff_markdown(config, range, files, languages)?;
+    Ok((changed_files, document))
+}
+
+/// Returns the file name of a part of a split output file, e.g. `name.part2.md`.
+fn part_file_name(output_file: &Path, number: usize) -> String {
+    let stem = output_file.file_stem().unwrap_or_default().to_string_lossy();
+    match output_file.extension() {
+        Some(ext) => format!("{}.part{}.{}", stem, number, ext.to_string_lossy()),
+        None => format!("{}.part{}", stem, number),
+    }
 }
@@ -1,84 +1,1049 @@
 use std::path::{Path, PathBuf};
 use std::collections::HashMap;
 use anyhow::Result;
-use crate::tree_generator;
+use crate::config::Config;
+use crate::file_processor::FileContent;
+use crate::git_repository::GitMetadata;
+use crate::language::Languages;
+use crate::part_splitter::{self, PartBudget, Size};
+use crate::statistics::ProjectStats;
+use crate::token_counter::TokenStats;
+use crate::{language, tree_generator};
 
 /// Returns the localized heading for a given key.
-fn localized_text(key: &str, lang: &str) -> String {
+pub fn localized_text(key: &str, lang: &str) -> String {
     match (key, lang) {
         ("project_documentation", "zh_cn") => "项目文档".to_string(),
         ("project_documentation", _) => "Project Documentation".to_string(),
         ("project_file_tree", "zh_cn") => "项目文件树".to_string(),
         ("project_file_tree", _) => "Project File Tree".to_string(),
+        ("token_summary", "zh_cn") => "Token 统计".to_string(),
+        ("token_summary", _) => "Token Summary".to_string(),
+        ("encoding", "zh_cn") => "编码".to_string(),
+        ("encoding", _) => "Encoding".to_string(),
+        ("tokens", "zh_cn") => "Token 数".to_string(),
+        ("tokens", _) => "Tokens".to_string(),
+        ("total", "zh_cn") => "总计".to_string(),
+        ("total", _) => "Total".to_string(),
+        ("parts", "zh_cn") => "分卷".to_string(),
+        ("parts", _) => "Parts".to_string(),
+        ("part", "zh_cn") => "分卷".to_string(),
+        ("part", _) => "Part".to_string(),
+        ("omitted_files", "zh_cn") => "省略的文件".to_string(),
+        ("omitted_files", _) => "Omitted Files".to_string(),
+        ("omitted_intro", "zh_cn") => "以下文件因超出内容预算而被省略：".to_string(),
+        ("omitted_intro", _) => "The following files were omitted to fit the content budget:".to_string(),
+        ("table_of_contents", "zh_cn") => "目录".to_string(),
+        ("table_of_contents", _) => "Table of Contents".to_string(),
         ("project_files", "zh_cn") => "项目文件".to_string(),
         ("project_files", _) => "Project Files".to_string(),
         ("file_label", "zh_cn") => "文件".to_string(),
         ("file_label", _) => "File".to_string(),
+        ("binary_file", "zh_cn") => "二进制文件".to_string(),
+        ("binary_file", _) => "Binary file".to_string(),
+        ("branch", "zh_cn") => "分支".to_string(),
+        ("branch", _) => "Branch".to_string(),
+        ("detached_head", "zh_cn") => "分离的 HEAD".to_string(),
+        ("detached_head", _) => "detached HEAD".to_string(),
+        ("commit", "zh_cn") => "提交".to_string(),
+        ("commit", _) => "Commit".to_string(),
+        ("no_commits", "zh_cn") => "尚无提交".to_string(),
+        ("no_commits", _) => "no commits yet".to_string(),
+        ("working_tree", "zh_cn") => "工作区".to_string(),
+        ("working_tree", _) => "Working tree".to_string(),
+        ("clean", "zh_cn") => "干净".to_string(),
+        ("clean", _) => "clean".to_string(),
+        ("dirty", "zh_cn") => "有未提交的更改".to_string(),
+        ("dirty", _) => "uncommitted changes".to_string(),
+        ("generated_at", "zh_cn") => "生成时间".to_string(),
+        ("generated_at", _) => "Generated".to_string(),
+        ("changes", "zh_cn") => "变更".to_string(),
+        ("changes", _) => "Changes".to_string(),
+        ("changed_files", "zh_cn") => "变更的文件".to_string(),
+        ("changed_files", _) => "Changed Files".to_string(),
+        ("no_changes", "zh_cn") => "两个版本之间没有变更。".to_string(),
+        ("no_changes", _) => "No files changed between the two revisions.".to_string(),
+        ("file_type_changed", "zh_cn") => "文件类型或权限已更改".to_string(),
+        ("file_type_changed", _) => "File type or mode changed".to_string(),
+        ("empty_file", "zh_cn") => "空文件".to_string(),
+        ("empty_file", _) => "Empty file".to_string(),
+        ("statistics", "zh_cn") => "统计".to_string(),
+        ("statistics", _) => "Statistics".to_string(),
+        ("largest_files", "zh_cn") => "最大的文件".to_string(),
+        ("largest_files", _) => "Largest Files".to_string(),
+        ("language", "zh_cn") => "语言".to_string(),
+        ("language", _) => "Language".to_string(),
+        ("files", "zh_cn") => "文件数".to_string(),
+        ("files", _) => "Files".to_string(),
+        ("lines", "zh_cn") => "行数".to_string(),
+        ("lines", _) => "Lines".to_string(),
+        ("code_lines", "zh_cn") => "代码".to_string(),
+        ("code_lines", _) => "Code".to_string(),
+        ("comment_lines", "zh_cn") => "注释".to_string(),
+        ("comment_lines", _) => "Comments".to_string(),
+        ("blank_lines", "zh_cn") => "空行".to_string(),
+        ("blank_lines", _) => "Blank".to_string(),
+        ("size", "zh_cn") => "字节数".to_string(),
+        ("size", _) => "Bytes".to_string(),
+        ("bytes", "zh_cn") => "字节".to_string(),
+        ("bytes", _) => "bytes".to_string(),
         _ => key.to_string(),
     }
 }
 
+/// Returns a backtick code fence that cannot be closed by anything inside `content`.
+///
+/// The fence is at least three backticks long and one longer than the longest run
+/// of backticks found in the content.
+fn code_fence(content: &str) -> String {
+    let mut longest_run = 0;
+    let mut current_run = 0;
+    for c in content.chars() {
+        if c == '`' {
+            current_run += 1;
+            longest_run = longest_run.max(current_run);
+        } else {
+            current_run = 0;
+        }
+    }
+    "`".repeat((longest_run + 1).max(3))
+}
+
+/// Converts a heading into a GitHub-compatible anchor.
+///
+/// The heading is lowercased, punctuation other than `-` and `_` is removed and spaces
+/// become hyphens. Repeated anchors get a numeric suffix, as GitHub does.
+///
+/// # Arguments
+///
+/// * `heading` - The heading text, as written in the Markdown source.
+/// * `used_anchors` - Anchors generated so far, with their occurrence count.
+///
+/// # Returns
+///
+/// * `String` - The unique anchor for the heading.
+pub fn github_anchor(heading: &str, used_anchors: &mut HashMap<String, usize>) -> String {
+    let slug: String = heading
+        .to_lowercase()
+        .chars()
+        .filter(|c| c.is_alphanumeric() || *c == '-' || *c == '_' || *c == ' ')
+        .map(|c| if c == ' ' { '-' } else { c })
+        .collect();
+
+    let count = used_anchors.entry(slug.clone()).or_insert(0);
+    let anchor = if *count == 0 { slug } else { format!("{}-{}", slug, count) };
+    *count += 1;
+    anchor
+}
+
+/// A file section of the "Project Files" part of the document.
+struct FileSection<'a> {
+    /// Path of the documented file.
+    file_path: &'a Path,
+    /// Label of the section, e.g. "`src/main.rs`" or "`src/main.rs` (L1-200)".
+    label: String,
+    /// Heading of the section, without the leading `###`.
+    heading: String,
+    /// Text following the label in the heading, e.g. " — Alice, 2024-05-01".
+    annotation: String,
+    /// Content of the section following its heading.
+    body: String,
+    /// Number of content lines, which bounds how finely the section can be split.
+    lines: usize,
+}
+
+impl FileSection<'_> {
+    /// Renders the section, including its heading.
+    fn render(&self) -> String {
+        format!("### {}\n\n{}", self.heading, self.body)
+    }
+}
+
+/// Placement of a rendered document within a split document.
+struct PartContext<'a> {
+    /// One-based number of the part.
+    number: usize,
+    /// Total number of parts.
+    total: usize,
+    /// Links of every file to its section, across all parts.
+    links: &'a HashMap<PathBuf, String>,
+}
+
+/// A Markdown document split into size-bounded parts.
+pub struct MarkdownParts {
+    /// Index document linking all parts, or the whole document if it fits in one part.
+    pub index: String,
+    /// Content of each part; empty if the document fits in one part.
+    pub parts: Vec<String>,
+}
+
 /// Generates Markdown documentation for a project based on its files and directories.
 ///
 /// # Arguments
 ///
-/// * `project_name` - Name of the project.
+/// * `config` - The project configuration.
 /// * `files` - List of files with their paths and contents.
-/// * `languages` - Mapping of file extensions to language names.
-/// * `project_root` - Path to the project root directory.
-/// * `lang` - Output language: "zh_cn" or "en_us" (default).
+/// * `languages` - Language definitions.
+/// * `token_stats` - Token counts to summarize at the top of the document (optional).
+/// * `git_metadata` - Repository revision to describe in the header (optional).
 ///
 /// # Returns
 ///
 /// * `Result<String>` - The generated Markdown content or an error.
 pub fn generate_markdown(
-    project_name: &str,
-    files: Vec<(PathBuf, String)>,
-    languages: &HashMap<String, String>,
-    project_root: &Path,
-    lang: &str,
+    config: &Config,
+    files: Vec<(PathBuf, FileContent)>,
+    languages: &Languages,
+    token_stats: Option<&TokenStats>,
+    git_metadata: Option<&GitMetadata>,
 ) -> Result<String> {
     // Sort files for consistent output
     let mut sorted_files = files;
     sorted_files.sort_by(|a, b| a.0.cmp(&b.0));
 
-    let title = localized_text("project_documentation", lang);
-    let mut markdown_content = format!("# {} for {}\n\n", title, project_name);
+    let statistics = config.statistics.then(|| ProjectStats::collect(&sorted_files, languages));
+    let sections = file_sections(config, &sorted_files, languages, git_metadata);
+    let section_refs: Vec<&FileSection> = sections.iter().collect();
+    render_document(config, &sorted_files, &section_refs, token_stats, statistics.as_ref(), git_metadata, None)
+}
 
-    // Add the project file tree to the Markdown (at the top)
-    let tree_heading = localized_text("project_file_tree", lang);
-    markdown_content.push_str(&format!("## {}\n\n", tree_heading));
-    markdown_content.push_str("```\n");
-    markdown_content.push_str(&format!("{}\n", project_name));
-    markdown_content.push_str(&tree_generator::generate_tree(project_name, &sorted_files, project_root)?);
-    markdown_content.push_str("```\n\n");
+/// Generates Markdown documentation split into parts that fit the configured budget.
+///
+/// Every part repeats the header and the full project tree. Files are packed in order
+/// and only split across parts, by lines, when a single file exceeds the budget.
+///
+/// # Arguments
+///
+/// * `config` - The project configuration.
+/// * `files` - List of files with their paths and contents.
+/// * `languages` - Language definitions.
+/// * `token_stats` - Token counts to summarize in the index (optional).
+/// * `git_metadata` - Repository revision to describe in every header (optional).
+/// * `budget` - Maximum size of each part.
+/// * `part_file_name` - Returns the file name of a part from its one-based number.
+///
+/// # Returns
+///
+/// * `Result<MarkdownParts>` - The index and the parts, or an error.
+pub fn generate_markdown_parts(
+    config: &Config,
+    files: Vec<(PathBuf, FileContent)>,
+    languages: &Languages,
+    token_stats: Option<&TokenStats>,
+    git_metadata: Option<&GitMetadata>,
+    budget: &PartBudget,
+    part_file_name: &dyn Fn(usize) -> String,
+) -> Result<MarkdownParts> {
+    let project_root = config.project_path.as_path();
+    let lang = config.markdown_lang.as_str();
 
-    // Add file contents to the Markdown
-    let files_heading = localized_text("project_files", lang);
+    // Sort files for consistent output
+    let mut sorted_files = files;
+    sorted_files.sort_by(|a, b| a.0.cmp(&b.0));
+
+    // Estimate what every part repeats, with links made of the file paths
+    let placeholder_links: HashMap<PathBuf, String> = sorted_files
+        .iter()
+        .map(|(file_path, _)| (file_path.clone(), format!("{}#{}", part_file_name(sorted_files.len()), file_path.display())))
+        .collect();
+    let placeholder = PartContext { number: sorted_files.len(), total: sorted_files.len(), links: &placeholder_links };
+    let mut overhead = budget.measure(&render_document(config, &sorted_files, &[], None, None, git_metadata, Some(&placeholder))?);
+    let statistics = config.statistics.then(|| ProjectStats::collect(&sorted_files, languages));
+
+    // Rendered parts can exceed the estimate, e.g. with long links or anchor suffixes;
+    // they are split again with the excess added to the overhead
+    let (sections, packed, parts) = loop {
+        if !budget.fits(overhead, Size::default()) {
+            anyhow::bail!("Part budget is too small to hold the document header and file tree");
+        }
+
+        // Split files that exceed the budget on their own
+        let mut sections = Vec::new();
+        for section in file_sections(config, &sorted_files, languages, git_metadata) {
+            let size = section_size(config, &section, budget);
+            if budget.fits(overhead, size) {
+                sections.push(section);
+                continue;
+            }
+            let text = sorted_files
+                .iter()
+                .find(|(file_path, _)| file_path == section.file_path)
+                .and_then(|(_, content)| content.as_text());
+            match text {
+                Some(text) => sections.extend(split_section(config, &section, text, languages, overhead, budget)),
+                None => sections.push(section),
+            }
+        }
+
+        let sizes: Vec<Size> = sections.iter().map(|s| section_size(config, s, budget)).collect();
+        let packed = part_splitter::pack(&sizes, overhead, budget);
+
+        // A document that fits in one part is not split, except for files exceeding the budget
+        if packed.len() <= 1 {
+            let section_refs: Vec<&FileSection> = sections.iter().collect();
+            let index = render_document(config, &sorted_files, &section_refs, token_stats, statistics.as_ref(), git_metadata, None)?;
+            return Ok(MarkdownParts { index, parts: Vec::new() });
+        }
+
+        let parts = render_parts(config, &sorted_files, &sections, &packed, git_metadata, part_file_name)?;
+
+        // Parts holding a single line cannot shrink further
+        let excess = packed
+            .iter()
+            .zip(&parts)
+            .filter(|(indices, _)| indices.len() > 1 || sections[indices[0]].lines > 1)
+            .map(|(_, part)| budget.excess(budget.measure(part)))
+            .fold(Size::default(), Size::max);
+        if excess == Size::default() {
+            break (sections, packed, parts);
+        }
+        overhead = overhead + excess;
+    };
+
+    // Build the index linking all parts
+    let title = document_title(config, None);
+    let mut index = format!("# {}\n\n", title);
+    if let Some(metadata) = git_metadata {
+        index.push_str(&render_git_metadata(config, metadata));
+    }
+    if let Some(stats) = token_stats {
+        index.push_str(&render_token_summary(config, stats));
+    }
+    if let Some(stats) = &statistics {
+        index.push_str(&render_statistics(config, stats));
+    }
+    index.push_str(&format!("## {}\n\n", localized_text("parts", lang)));
+    for (part_index, indices) in packed.iter().enumerate() {
+        let first = sections[indices[0]].file_path;
+        let last = sections[indices[indices.len() - 1]].file_path;
+        index.push_str(&format!(
+            "- [{} {}]({}): `{}` – `{}`\n",
+            localized_text("part", lang),
+            part_index + 1,
+            part_file_name(part_index + 1),
+            first.strip_prefix(project_root).unwrap_or(first).display(),
+            last.strip_prefix(project_root).unwrap_or(last).display()
+        ));
+    }
+    let omitted = render_omitted_files(config, &sorted_files);
+    if !omitted.is_empty() {
+        index.push('\n');
+        index.push_str(&omitted);
+    }
+
+    Ok(MarkdownParts { index, parts })
+}
+
+/// Renders the parts of a split document.
+///
+/// # Arguments
+///
+/// * `config` - The project configuration.
+/// * `files` - All files of the project, used for the tree.
+/// * `sections` - File sections of the document.
+/// * `packed` - Indices of the sections in each part.
+/// * `git_metadata` - Repository revision to describe in every header (optional).
+/// * `part_file_name` - Returns the file name of a part from its one-based number.
+///
+/// # Returns
+///
+/// * `Result<Vec<String>>` - The content of each part or an error.
+fn render_parts(
+    config: &Config,
+    files: &[(PathBuf, FileContent)],
+    sections: &[FileSection],
+    packed: &[Vec<usize>],
+    git_metadata: Option<&GitMetadata>,
+    part_file_name: &dyn Fn(usize) -> String,
+) -> Result<Vec<String>> {
+    let total = packed.len();
+
+    // Link every file to its first section, in whichever part it lands
+    let mut links = HashMap::new();
+    for (part_index, indices) in packed.iter().enumerate() {
+        let part = PartContext { number: part_index + 1, total, links: &links };
+        let part_sections: Vec<&FileSection> = indices.iter().map(|&i| &sections[i]).collect();
+        let anchors = section_anchors(config, &part_sections, false, false, Some(&part));
+        let mut part_links = Vec::new();
+        for (section, anchor) in part_sections.iter().zip(anchors) {
+            part_links.push((section.file_path.to_path_buf(), format!("{}#{}", part_file_name(part_index + 1), anchor)));
+        }
+        for (file_path, link) in part_links {
+            links.entry(file_path).or_insert(link);
+        }
+    }
+
+    let mut parts = Vec::with_capacity(total);
+    for (part_index, indices) in packed.iter().enumerate() {
+        let part = PartContext { number: part_index + 1, total, links: &links };
+        let part_sections: Vec<&FileSection> = indices.iter().map(|&i| &sections[i]).collect();
+        parts.push(render_document(config, files, &part_sections, None, None, git_metadata, Some(&part))?);
+    }
+    Ok(parts)
+}
+
+/// Returns the title of the document, with the part number for split documents.
+fn document_title(config: &Config, part: Option<&PartContext>) -> String {
+    let lang = config.markdown_lang.as_str();
+    let title = project_title(config);
+    match part {
+        Some(part) => format!("{} ({} {}/{})", title, localized_text("part", lang), part.number, part.total),
+        None => title,
+    }
+}
+
+/// Returns the title of a project's documentation.
+pub fn project_title(config: &Config) -> String {
+    format!("{} for {}", localized_text("project_documentation", &config.markdown_lang), config.project_name)
+}
+
+/// Builds one section per file, in order.
+fn file_sections<'a>(
+    config: &Config,
+    files: &'a [(PathBuf, FileContent)],
+    languages: &Languages,
+    git_metadata: Option<&GitMetadata>,
+) -> Vec<FileSection<'a>> {
+    let project_root = config.project_path.as_path();
+    let lang = config.markdown_lang.as_str();
     let file_label = localized_text("file_label", lang);
-    markdown_content.push_str(&format!("## {}\n\n", files_heading));
-    for (file_path, content) in &sorted_files {
-        // Get the relative path of the file with respect to the project root
+    let binary_label = localized_text("binary_file", lang);
+    let bytes_label = localized_text("bytes", lang);
+
+    files
+        .iter()
+        .filter_map(|(file_path, content)| {
+            // Get the relative path of the file with respect to the project root
+            let relative_path = file_path.strip_prefix(project_root).unwrap_or(file_path);
+            let first_line = first_line(config, file_path);
+            let label = match excerpt_lines(config, file_path, content) {
+                // Excerpts are labeled with the lines they contain
+                Some((first_line, last_line)) => format!("`{}` (L{}-{})", relative_path.display(), first_line, last_line),
+                None => format!("`{}`", relative_path.display()),
+            };
+
+            let body = match content {
+                // Binary files are rendered as a metadata stub instead of a code block
+                FileContent::Binary(info) => format!(
+                    "> {}: {} {}, `{}`, SHA-256 `{}`\n\n",
+                    binary_label,
+                    info.size,
+                    bytes_label,
+                    info.mime_type,
+                    info.sha256
+                ),
+                FileContent::Text(text) => {
+                    // Determine the corresponding language from the file extension
+                    let language = language::detect_language(file_path, Some(text), languages);
+                    if config.line_numbers {
+                        let width = line_number_width(first_line, text);
+                        code_block(&language.fence, &number_lines(text, first_line, width))
+                    } else {
+                        code_block(&language.fence, text)
+                    }
+                }
+                // Omitted files have no section; they are listed under "Omitted Files"
+                FileContent::Omitted { .. } => return None,
+            };
+
+            let annotation = commit_annotation(git_metadata, file_path);
+
+            Some(FileSection {
+                file_path,
+                heading: format!("{}: {}{}", file_label, label, annotation),
+                label,
+                annotation,
+                body,
+                lines: content.as_text().map_or(0, |text| text.split_inclusive('\n').count()),
+            })
+        })
+        .collect()
+}
+
+/// Returns the first and last line numbers of an excerpt, or `None` if the content is
+/// not an excerpt of a text file.
+pub fn excerpt_lines(config: &Config, file_path: &Path, content: &FileContent) -> Option<(usize, usize)> {
+    let lines = config.line_range(file_path)?;
+    let text = content.as_text()?;
+    Some((lines.start, lines.start + text.split_inclusive('\n').count().saturating_sub(1)))
+}
+
+/// Returns the text following the file label in a section heading, naming the last
+/// commit of the file if known (e.g., " — Alice, 2024-05-01").
+pub fn commit_annotation(git_metadata: Option<&GitMetadata>, file_path: &Path) -> String {
+    git_metadata
+        .and_then(|metadata| metadata.last_commits.get(file_path))
+        .map(|commit| format!(" — {}, {}", commit.author, commit.date))
+        .unwrap_or_default()
+}
+
+/// Returns the number of the first line of a file's content: the start of its line
+/// range for excerpts, 1 otherwise.
+pub fn first_line(config: &Config, file_path: &Path) -> usize {
+    config.line_range(file_path).map_or(1, |lines| lines.start)
+}
+
+/// Returns the number of digits of the last line number of a text.
+pub fn line_number_width(first_line: usize, text: &str) -> usize {
+    let last_line = first_line + text.split_inclusive('\n').count().saturating_sub(1);
+    last_line.to_string().len()
+}
+
+/// Prefixes a line with its number, right-aligned to `width` digits.
+pub fn number_line(number: usize, width: usize, line: &str) -> String {
+    format!("{:>width$} | {}", number, line, width = width)
+}
+
+/// Prefixes each line of a text with its number, starting at `first_line`.
+fn number_lines(text: &str, first_line: usize, width: usize) -> String {
+    text.split_inclusive('\n')
+        .enumerate()
+        .map(|(index, line)| number_line(first_line + index, width, line))
+        .collect()
+}
+
+/// Renders content as a fenced code block followed by a blank line.
+pub fn code_block(language: &str, content: &str) -> String {
+    let fence = code_fence(content);
+    format!("{}{}\n{}\n{}\n\n", fence, language, content, fence)
+}
+
+/// Splits the section of a text file into sections that each fit the budget, by lines.
+///
+/// # Arguments
+///
+/// * `config` - The project configuration.
+/// * `section` - The section of the whole file.
+/// * `text` - Content of the file.
+/// * `languages` - Language definitions.
+/// * `overhead` - Size repeated in every part.
+/// * `budget` - Maximum size of each part.
+///
+/// # Returns
+///
+/// * `Vec<FileSection>` - Sections labeled with the line range they contain.
+fn split_section<'a>(
+    config: &Config,
+    section: &FileSection<'a>,
+    text: &str,
+    languages: &Languages,
+    overhead: Size,
+    budget: &PartBudget,
+) -> Vec<FileSection<'a>> {
+    let file_label = localized_text("file_label", &config.markdown_lang);
+    let language = language::detect_language(section.file_path, Some(text), languages);
+    let relative_path = section.file_path.strip_prefix(&config.project_path).unwrap_or(section.file_path);
+    // Excerpts keep the line numbers of the whole file
+    let first_line = first_line(config, section.file_path);
+    let width = line_number_width(first_line, text);
+    let make_section = |start: usize, end: usize, lines: &str| {
+        let label = format!("`{}` (L{}-{})", relative_path.display(), start, end);
+        FileSection {
+            file_path: section.file_path,
+            heading: format!("{}: {}{}", file_label, label, section.annotation),
+            label,
+            annotation: section.annotation.clone(),
+            body: code_block(&language.fence, lines.strip_suffix('\n').unwrap_or(lines)),
+            lines: end + 1 - start,
+        }
+    };
+
+    // Headings and fences of a chunk, measured once with the widest line numbers
+    let last_line = first_line + text.split_inclusive('\n').count().saturating_sub(1);
+    let chunk_overhead = overhead + section_size(config, &make_section(last_line, last_line, ""), budget);
+
+    let mut sections = Vec::new();
+    let mut chunk = String::new();
+    let mut chunk_start = first_line;
+    let mut used = chunk_overhead;
+    for (index, line) in text.split_inclusive('\n').enumerate() {
+        let number = first_line + index;
+        let line = if config.line_numbers { number_line(number, width, line) } else { line.to_string() };
+        let line_size = budget.measure(&line);
+        if !chunk.is_empty() && !budget.fits(used, line_size) {
+            sections.push(make_section(chunk_start, number - 1, &chunk));
+            chunk.clear();
+            chunk_start = number;
+            used = chunk_overhead;
+        }
+        chunk.push_str(&line);
+        used = used + line_size;
+    }
+    if !chunk.is_empty() {
+        sections.push(make_section(chunk_start, last_line, &chunk));
+    }
+
+    log::info!("Split {} into {} sections to fit the part budget", section.file_path.display(), sections.len());
+    sections
+}
+
+/// Measures a section together with its table of contents entry.
+///
+/// The anchor of the entry may get a suffix once the section is placed in a part, so
+/// rendered parts are measured again.
+fn section_size(config: &Config, section: &FileSection, budget: &PartBudget) -> Size {
+    let mut size = budget.measure(&section.render());
+    if config.table_of_contents {
+        let anchor = github_anchor(&section.heading, &mut HashMap::new());
+        size = size + budget.measure(&format!("- [{}](#{})\n", section.label, anchor));
+    }
+    size
+}
+
+/// Computes the anchors of the given sections, as they appear in the rendered document.
+fn section_anchors(
+    config: &Config,
+    sections: &[&FileSection],
+    has_token_summary: bool,
+    has_statistics: bool,
+    part: Option<&PartContext>,
+) -> Vec<String> {
+    let title = document_title(config, part);
+    let mut used_anchors = heading_anchors(config, &title, has_token_summary, has_statistics);
+    sections
+        .iter()
+        .map(|section| github_anchor(&section.heading, &mut used_anchors))
+        .collect()
+}
+
+/// Returns the anchors used by the headings preceding the file sections of a document,
+/// so that file anchors computed afterwards match the rendered document.
+///
+/// # Arguments
+///
+/// * `config` - The project configuration.
+/// * `title` - Title of the document.
+/// * `has_token_summary` - Whether the document has a token summary.
+/// * `has_statistics` - Whether the document has a statistics section.
+///
+/// # Returns
+///
+/// * `HashMap<String, usize>` - The used anchors, for `github_anchor`.
+pub fn heading_anchors(config: &Config, title: &str, has_token_summary: bool, has_statistics: bool) -> HashMap<String, usize> {
+    let lang = config.markdown_lang.as_str();
+    let mut used_anchors = HashMap::new();
+    let mut headings = vec![
+        title.to_string(),
+        localized_text("project_file_tree", lang),
+        localized_text("table_of_contents", lang),
+        localized_text("project_files", lang),
+    ];
+    if has_token_summary {
+        headings.push(localized_text("token_summary", lang));
+    }
+    if has_statistics {
+        headings.push(localized_text("statistics", lang));
+        headings.push(localized_text("largest_files", lang));
+    }
+    for heading in &headings {
+        github_anchor(heading, &mut used_anchors);
+    }
+    used_anchors
+}
+
+/// Renders the list of files omitted to fit the content budget, if any.
+fn render_omitted_files(config: &Config, files: &[(PathBuf, FileContent)]) -> String {
+    let project_root = config.project_path.as_path();
+    let lang = config.markdown_lang.as_str();
+
+    let omitted: Vec<(&PathBuf, u64)> = files
+        .iter()
+        .filter_map(|(file_path, content)| match content {
+            FileContent::Omitted { size } => Some((file_path, *size)),
+            _ => None,
+        })
+        .collect();
+    if omitted.is_empty() {
+        return String::new();
+    }
+
+    let mut list = format!("## {}\n\n{}\n\n", localized_text("omitted_files", lang), localized_text("omitted_intro", lang));
+    for (file_path, size) in omitted {
         let relative_path = file_path.strip_prefix(project_root).unwrap_or(file_path);
-        let display_path = relative_path.display();
-
-        // Determine the file extension and corresponding language
-        let extension = file_path
-            .extension()
-            .unwrap_or_default()
-            .to_string_lossy()
-            .to_lowercase();
-
-        let language = languages.get(&extension).unwrap_or(&"Text".to_string()).clone();
-
-        markdown_content.push_str(&format!(
-            "### {}: `{}`\n\n```{}\n{}\n```\n\n",
-            file_label,
-            display_path,
-            language,
-            content
+        list.push_str(&format!("- `{}` ({} {})\n", relative_path.display(), size, localized_text("bytes", lang)));
+    }
+    list.push('\n');
+    list
+}
+
+/// Renders the repository revision as a list of fields.
+fn render_git_metadata(config: &Config, metadata: &GitMetadata) -> String {
+    let lang = config.markdown_lang.as_str();
+    let branch = metadata
+        .branch
+        .as_ref()
+        .map_or_else(|| localized_text("detached_head", lang), |branch| format!("`{}`", branch));
+    let commit = metadata
+        .head
+        .as_ref()
+        .map_or_else(|| localized_text("no_commits", lang), |head| format!("`{}`", head));
+    let status = localized_text(if metadata.dirty { "dirty" } else { "clean" }, lang);
+
+    format!(
+        "- **{}**: {}\n- **{}**: {}\n- **{}**: {}\n- **{}**: {}\n\n",
+        localized_text("branch", lang),
+        branch,
+        localized_text("commit", lang),
+        commit,
+        localized_text("working_tree", lang),
+        status,
+        localized_text("generated_at", lang),
+        metadata.generated_at
+    )
+}
+
+/// Renders the token summary table, largest files first.
+fn render_token_summary(config: &Config, stats: &TokenStats) -> String {
+    let project_root = config.project_path.as_path();
+    let lang = config.markdown_lang.as_str();
+
+    let mut summary = format!("## {}\n\n", localized_text("token_summary", lang));
+    summary.push_str(&format!(
+        "{}: `{}`, {}: **{}**\n\n",
+        localized_text("encoding", lang),
+        stats.encoding.name(),
+        localized_text("total", lang),
+        stats.total
+    ));
+    summary.push_str(&format!(
+        "| {} | {} |\n|---|---:|\n",
+        localized_text("file_label", lang),
+        localized_text("tokens", lang)
+    ));
+    let mut file_tokens: Vec<&(PathBuf, usize)> = stats.files.iter().collect();
+    file_tokens.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    for (file_path, tokens) in file_tokens {
+        let relative_path = file_path.strip_prefix(project_root).unwrap_or(file_path);
+        summary.push_str(&format!("| `{}` | {} |\n", relative_path.display(), tokens));
+    }
+    summary.push('\n');
+    summary
+}
+
+/// Renders the statistics table per language, followed by the largest files.
+fn render_statistics(config: &Config, stats: &ProjectStats) -> String {
+    let project_root = config.project_path.as_path();
+    let lang = config.markdown_lang.as_str();
+
+    let mut table = format!("## {}\n\n", localized_text("statistics", lang));
+    table.push_str(&format!(
+        "| {} | {} | {} | {} | {} | {} | {} |\n|---|---:|---:|---:|---:|---:|---:|\n",
+        localized_text("language", lang),
+        localized_text("files", lang),
+        localized_text("lines", lang),
+        localized_text("code_lines", lang),
+        localized_text("comment_lines", lang),
+        localized_text("blank_lines", lang),
+        localized_text("size", lang)
+    ));
+    let total = stats.total();
+    let rows = stats
+        .languages
+        .iter()
+        .map(|row| (row.language.clone(), row))
+        .chain(std::iter::once((format!("**{}**", localized_text("total", lang)), &total)));
+    for (label, row) in rows {
+        table.push_str(&format!(
+            "| {} | {} | {} | {} | {} | {} | {} |\n",
+            label,
+            row.files,
+            row.lines.total(),
+            row.lines.code,
+            row.lines.comment,
+            row.lines.blank,
+            row.bytes
         ));
     }
 
+    table.push_str(&format!("\n### {}\n\n", localized_text("largest_files", lang)));
+    table.push_str(&format!(
+        "| {} | {} | {} | {} |\n|---|---|---:|---:|\n",
+        localized_text("file_label", lang),
+        localized_text("language", lang),
+        localized_text("lines", lang),
+        localized_text("size", lang)
+    ));
+    for file in &stats.largest_files {
+        let relative_path = file.path.strip_prefix(project_root).unwrap_or(&file.path);
+        table.push_str(&format!("| `{}` | {} | {} | {} |\n", relative_path.display(), file.language, file.lines, file.bytes));
+    }
+    table.push('\n');
+    table
+}
+
+/// Renders a complete Markdown document, or one part of a split document.
+///
+/// # Arguments
+///
+/// * `config` - The project configuration.
+/// * `files` - All files of the project, used for the tree.
+/// * `sections` - File sections included in this document.
+/// * `token_stats` - Token counts to summarize at the top of the document (optional).
+/// * `statistics` - Line and byte counts per language to summarize (optional).
+/// * `git_metadata` - Repository revision to describe in the header (optional).
+/// * `part` - Placement of the document within a split document (optional).
+///
+/// # Returns
+///
+/// * `Result<String>` - The rendered Markdown content or an error.
+fn render_document(
+    config: &Config,
+    files: &[(PathBuf, FileContent)],
+    sections: &[&FileSection],
+    token_stats: Option<&TokenStats>,
+    statistics: Option<&ProjectStats>,
+    git_metadata: Option<&GitMetadata>,
+    part: Option<&PartContext>,
+) -> Result<String> {
+    let project_name = config.project_name.as_str();
+    let project_root = config.project_path.as_path();
+    let lang = config.markdown_lang.as_str();
+
+    // Compute the section anchors up front so the tree and the table of contents can
+    // link to them
+    let anchors = section_anchors(config, sections, token_stats.is_some(), statistics.is_some(), part);
+    let local_links: HashMap<PathBuf, String> = sections
+        .iter()
+        .zip(&anchors)
+        .rev()
+        .map(|(section, anchor)| (section.file_path.to_path_buf(), format!("#{}", anchor)))
+        .collect();
+    let links = part.map_or(&local_links, |part| part.links);
+
+    let mut markdown_content = format!("# {}\n\n", document_title(config, part));
+
+    // Add the repository revision
+    if let Some(metadata) = git_metadata {
+        markdown_content.push_str(&render_git_metadata(config, metadata));
+    }
+
+    // Add the token summary
+    if let Some(stats) = token_stats {
+        markdown_content.push_str(&render_token_summary(config, stats));
+    }
+
+    // Add the statistics
+    if let Some(stats) = statistics {
+        markdown_content.push_str(&render_statistics(config, stats));
+    }
+
+    // Add the project file tree to the Markdown (at the top)
+    markdown_content.push_str(&format!("## {}\n\n", localized_text("project_file_tree", lang)));
+    if config.linked_tree {
+        markdown_content.push_str(&tree_generator::generate_linked_tree(project_name, files, project_root, links)?);
+        markdown_content.push('\n');
+    } else {
+        let tree = format!(
+            "{}\n{}",
+            project_name,
+            tree_generator::generate_tree(project_name, files, project_root)?
+        );
+        let tree_fence = code_fence(&tree);
+        markdown_content.push_str(&format!("{}\n{}{}\n\n", tree_fence, tree, tree_fence));
+    }
+
+    // Add the table of contents
+    if config.table_of_contents {
+        markdown_content.push_str(&format!("## {}\n\n", localized_text("table_of_contents", lang)));
+        for (section, anchor) in sections.iter().zip(&anchors) {
+            markdown_content.push_str(&format!("- [{}](#{})\n", section.label, anchor));
+        }
+        markdown_content.push('\n');
+    }
+
+    // Add file contents to the Markdown
+    markdown_content.push_str(&format!("## {}\n\n", localized_text("project_files", lang)));
+    for section in sections {
+        markdown_content.push_str(&section.render());
+    }
+
+    // List omitted files at the end; split documents list them in the index instead
+    if part.is_none() {
+        markdown_content.push_str(&render_omitted_files(config, files));
+    }
+
     Ok(markdown_content)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::test_config;
+
+    #[test]
+    fn test_code_fence() {
+        assert_eq!(code_fence("fn main() {}"), "```");
+        assert_eq!(code_fence("use `code` here"), "```");
+        assert_eq!(code_fence("```rust\nfn main() {}\n```"), "````");
+        assert_eq!(code_fence("``````"), "```````");
+    }
+
+    #[test]
+    fn test_generate_markdown_with_nested_fences() {
+        let root = Path::new("/project");
+        let readme = "# Readme\n\n```bash\ncargo build\n```".to_string();
+        let files = vec![(root.join("README.md"), FileContent::Text(readme.clone()))];
+        let languages = Languages::from(HashMap::from([("md".to_string(), "Markdown".to_string())]));
+
+        let markdown = generate_markdown(&test_config(""), files, &languages, None, None).unwrap();
+        assert!(markdown.contains(&format!("````markdown\n{}\n````\n", readme)));
+    }
+
+    #[test]
+    fn test_github_anchor() {
+        let mut used = HashMap::new();
+        assert_eq!(github_anchor("File: `src/main.rs`", &mut used), "file-srcmainrs");
+        assert_eq!(github_anchor("File: `src/main.rs`", &mut used), "file-srcmainrs-1");
+        assert_eq!(github_anchor("文件: `my_mod/lib-a.rs`", &mut used), "文件-my_modlib-ars");
+    }
+
+    #[test]
+    fn test_generate_markdown_with_omitted_files() {
+        let root = Path::new("/project");
+        let files = vec![
+            (root.join("src/main.rs"), FileContent::Text("fn main() {}".to_string())),
+            (root.join("data.csv"), FileContent::Omitted { size: 2048 }),
+        ];
+
+        let markdown = generate_markdown(&test_config(""), files, &Languages::default(), None, None).unwrap();
+        assert!(markdown.contains("### File: `src/main.rs`\n"));
+        assert!(!markdown.contains("### File: `data.csv`"));
+        assert!(markdown.contains("data.csv [omitted]"));
+        assert!(markdown.contains("## Omitted Files"));
+    }
+
+    #[test]
+    fn test_generate_markdown_with_links() {
+        let root = Path::new("/project");
+        let files = vec![
+            (root.join("src/main.rs"), FileContent::Text("fn main() {}".to_string())),
+            (root.join("Cargo.toml"), FileContent::Text("[package]".to_string())),
+        ];
+        let config = test_config("linked_tree: true");
+
+        let markdown = generate_markdown(&config, files, &Languages::default(), None, None).unwrap();
+        assert!(markdown.contains("## Table of Contents\n\n- [`Cargo.toml`](#file-cargotoml)\n- [`src/main.rs`](#file-srcmainrs)\n"));
+        assert!(markdown.contains("  - [main.rs](#file-srcmainrs)\n"));
+        assert!(markdown.contains("### File: `src/main.rs`\n"));
+    }
+
+    #[test]
+    fn test_generate_markdown_with_line_numbers() {
+        let root = Path::new("/project");
+        let files = vec![
+            (root.join("src/main.rs"), FileContent::Text("fn a() {}\nfn b() {}\n".to_string())),
+            (root.join("build.rs"), FileContent::Text("fn main() {}\n".to_string())),
+        ];
+        let mut config = test_config("line_numbers: true");
+        config.files = vec![serde_yaml::from_str("src/main.rs#L9-12").unwrap()];
+
+        let markdown = generate_markdown(&config, files, &language::default_languages(), None, None).unwrap();
+        assert!(markdown.contains("- [`src/main.rs` (L9-10)](#file-srcmainrs-l9-10)\n"));
+        assert!(markdown.contains("### File: `src/main.rs` (L9-10)\n\n```rust\n 9 | fn a() {}\n10 | fn b() {}\n\n```\n"));
+        assert!(markdown.contains("### File: `build.rs`\n\n```rust\n1 | fn main() {}\n\n```\n"));
+    }
+
+    #[test]
+    fn test_generate_markdown_with_statistics() {
+        let root = Path::new("/project");
+        let files = vec![
+            (root.join("src/main.rs"), FileContent::Text("// entry\n\nfn main() {}\n".to_string())),
+            (root.join("run.sh"), FileContent::Text("#!/bin/bash\necho hi\n".to_string())),
+        ];
+        let config = test_config("statistics: true");
+
+        let markdown = generate_markdown(&config, files, &language::default_languages(), None, None).unwrap();
+        assert!(markdown.contains("## Statistics\n\n| Language | Files | Lines | Code | Comments | Blank | Bytes |\n"));
+        assert!(markdown.contains("| Rust | 1 | 3 | 1 | 1 | 1 | 23 |\n| Shell | 1 | 2 | 1 | 1 | 0 | 20 |\n| **Total** | 2 | 5 | 2 | 2 | 1 | 43 |\n"));
+        assert!(markdown.contains("### Largest Files\n\n| File | Language | Lines | Bytes |\n|---|---|---:|---:|\n| `src/main.rs` | Rust | 3 | 23 |\n"));
+        assert!(markdown.contains("- [`src/main.rs`](#file-srcmainrs)\n"));
+    }
+
+    #[test]
+    fn test_generate_markdown_with_git_metadata() {
+        use crate::git_repository::{CommitInfo, GitMetadata};
+
+        let root = Path::new("/project");
+        let files = vec![
+            (root.join("a.rs"), FileContent::Text("a".to_string())),
+            (root.join("b.rs"), FileContent::Text("b".to_string())),
+        ];
+        let commit = CommitInfo { author: "Alice".to_string(), date: "2024-05-01".to_string() };
+        let metadata = GitMetadata {
+            branch: None,
+            head: Some("abc123".to_string()),
+            dirty: true,
+            generated_at: "2024-05-02 10:00:00 +00:00".to_string(),
+            last_commits: HashMap::from([(root.join("a.rs"), commit)]),
+        };
+
+        let markdown = generate_markdown(&test_config(""), files, &Languages::default(), None, Some(&metadata)).unwrap();
+        assert!(markdown.contains("- **Branch**: detached HEAD\n- **Commit**: `abc123`\n- **Working tree**: uncommitted changes\n"));
+        assert!(markdown.contains("### File: `a.rs` — Alice, 2024-05-01\n"));
+        assert!(markdown.contains("### File: `b.rs`\n"));
+        assert!(markdown.contains("(#file-ars--alice-2024-05-01)"));
+    }
+
+    #[test]
+    fn test_generate_markdown_parts() {
+        use crate::config::TokenEncoding;
+        use crate::token_counter::TokenCounter;
+
+        let root = Path::new("/project");
+        let big: String = (1..=40).map(|i| format!("line {}\n", i)).collect();
+        let files = vec![
+            (root.join("a.txt"), FileContent::Text("small".to_string())),
+            (root.join("b.txt"), FileContent::Text(big)),
+        ];
+        let config = test_config("max_bytes_per_part: 400");
+        let counter = TokenCounter::new(TokenEncoding::Estimate);
+        let budget = PartBudget::from_config(&config, &counter).unwrap();
+
+        let split = generate_markdown_parts(&config, files, &Languages::default(), None, None, &budget, &|n| format!("demo.part{}.md", n)).unwrap();
+        assert!(split.parts.len() > 2);
+        assert!(split.parts.iter().all(|part| part.len() <= 400));
+        assert!(split.parts[0].starts_with(&format!("# Project Documentation for Demo (Part 1/{})", split.parts.len())));
+        assert!(split.parts[0].contains("### File: `a.txt`\n"));
+        assert!(split.parts.concat().contains("### File: `b.txt` (L1-"));
+        assert!(split.parts.last().unwrap().contains("-40)\n"));
+        assert!(split.index.contains("- [Part 1](demo.part1.md): `a.txt` – "));
+    }
+
+    #[test]
+    fn test_generate_markdown_parts_within_budget() {
+        use crate::config::TokenEncoding;
+        use crate::git_repository::{CommitInfo, GitMetadata};
+        use crate::token_counter::TokenCounter;
+
+        // Linked tree entries point to anchors that include the last commit, longer than
+        // the placeholder links used to estimate the overhead
+        let root = Path::new("/project");
+        let files: Vec<(PathBuf, FileContent)> = (0..12)
+            .map(|i| (root.join(format!("f{:02}.rs", i)), FileContent::Text(format!("fn f{}() {{}}\n", i).repeat(3))))
+            .collect();
+        let commit = CommitInfo { author: "Maximilian Alexander Featherstonehaugh".to_string(), date: "2024-05-01".to_string() };
+        let metadata = GitMetadata {
+            branch: Some("main".to_string()),
+            head: Some("abc123".to_string()),
+            dirty: false,
+            generated_at: "2024-05-02 10:00:00 +00:00".to_string(),
+            last_commits: files.iter().map(|(file_path, _)| (file_path.clone(), commit.clone())).collect(),
+        };
+        let counter = TokenCounter::new(TokenEncoding::Estimate);
+
+        for max_bytes in [1800, 2200, 3000] {
+            let config = test_config(&format!("linked_tree: true\nmax_bytes_per_part: {}", max_bytes));
+            let budget = PartBudget::from_config(&config, &counter).unwrap();
+            let split = generate_markdown_parts(
+                &config, files.clone(), &Languages::default(), None, Some(&metadata), &budget, &|n| format!("demo.part{}.md", n),
+            ).unwrap();
+            assert!(split.parts.len() > 1);
+            for part in &split.parts {
+                assert!(part.len() <= max_bytes, "part of {} bytes exceeds {}", part.len(), max_bytes);
+            }
+            for (_, content) in &files {
+                assert!(split.parts.concat().contains(content.as_text().unwrap()));
+            }
+        }
+    }
+}
@@ -0,0 +1,135 @@
+use std::ops::Add;
+use crate::config::Config;
+use crate::token_counter::TokenCounter;
+
+/// Size of a piece of a document, in bytes and tokens.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Size {
+    pub bytes: u64,
+    pub tokens: usize,
+}
+
+impl Size {
+    /// Returns the larger of each measure of two sizes.
+    pub fn max(self, other: Size) -> Size {
+        Size {
+            bytes: self.bytes.max(other.bytes),
+            tokens: self.tokens.max(other.tokens),
+        }
+    }
+}
+
+impl Add for Size {
+    type Output = Size;
+
+    fn add(self, other: Size) -> Size {
+        Size {
+            bytes: self.bytes + other.bytes,
+            tokens: self.tokens + other.tokens,
+        }
+    }
+}
+
+/// Maximum size of each part of a split document.
+///
+/// Token counts of concatenated pieces are summed, so the token limit is approximate.
+pub struct PartBudget<'a> {
+    max_tokens: Option<usize>,
+    max_bytes: Option<u64>,
+    counter: &'a TokenCounter,
+}
+
+impl<'a> PartBudget<'a> {
+    /// Creates the part budget configured for a project.
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - The project configuration.
+    /// * `counter` - Token counter used to measure text.
+    ///
+    /// # Returns
+    ///
+    /// * `Option<PartBudget>` - The budget, or `None` if the document should not be split.
+    pub fn from_config(config: &Config, counter: &'a TokenCounter) -> Option<Self> {
+        if config.max_tokens_per_part.is_none() && config.max_bytes_per_part.is_none() {
+            return None;
+        }
+        Some(PartBudget {
+            max_tokens: config.max_tokens_per_part,
+            max_bytes: config.max_bytes_per_part,
+            counter,
+        })
+    }
+
+    /// Measures a piece of text. Tokens are only counted when a token limit is set.
+    pub fn measure(&self, text: &str) -> Size {
+        Size {
+            bytes: text.len() as u64,
+            tokens: if self.max_tokens.is_some() { self.counter.count(text) } else { 0 },
+        }
+    }
+
+    /// Returns how much a piece exceeds the budget, or a zero size if it fits.
+    pub fn excess(&self, size: Size) -> Size {
+        Size {
+            bytes: self.max_bytes.map_or(0, |max| size.bytes.saturating_sub(max)),
+            tokens: self.max_tokens.map_or(0, |max| size.tokens.saturating_sub(max)),
+        }
+    }
+
+    /// Returns `true` if a piece of the given size fits in a part after `used` is taken.
+    pub fn fits(&self, used: Size, size: Size) -> bool {
+        let total = used + size;
+        self.max_bytes.is_none_or(|max| total.bytes <= max)
+            && self.max_tokens.is_none_or(|max| total.tokens <= max)
+    }
+}
+
+/// Packs pieces into parts, in order, without exceeding the budget.
+///
+/// A piece that does not fit in an empty part gets a part of its own.
+///
+/// # Arguments
+///
+/// * `sizes` - Size of each piece.
+/// * `overhead` - Size repeated in every part (header, tree, ...).
+/// * `budget` - The part budget.
+///
+/// # Returns
+///
+/// * `Vec<Vec<usize>>` - Indices of the pieces in each part.
+pub fn pack(sizes: &[Size], overhead: Size, budget: &PartBudget) -> Vec<Vec<usize>> {
+    let mut parts: Vec<Vec<usize>> = Vec::new();
+    let mut current: Vec<usize> = Vec::new();
+    let mut used = overhead;
+
+    for (index, size) in sizes.iter().enumerate() {
+        if !current.is_empty() && !budget.fits(used, *size) {
+            parts.push(std::mem::take(&mut current));
+            used = overhead;
+        }
+        current.push(index);
+        used = used + *size;
+    }
+    if !current.is_empty() {
+        parts.push(current);
+    }
+
+    parts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::TokenEncoding;
+
+    #[test]
+    fn test_pack() {
+        let counter = TokenCounter::new(TokenEncoding::Estimate);
+        let budget = PartBudget { max_tokens: None, max_bytes: Some(100), counter: &counter };
+        let size = |bytes| Size { bytes, tokens: 0 };
+
+        let sizes = [size(30), size(40), size(30), size(150), size(10)];
+        assert_eq!(pack(&sizes, size(20), &budget), vec![vec![0, 1], vec![2], vec![3], vec![4]]);
+    }
+}
@@ -0,0 +1,316 @@
+use std::path::Path;
+use anyhow::{Context, Result};
+use serde::Serialize;
+use serde_yaml::{Mapping, Value};
+use crate::config::Config;
+
+/// Project settings given on the command line, for a project without a configuration
+/// file.
+///
+/// Each option mirrors a field of the project configuration; options that are not
+/// given keep the defaults of a configuration file.
+#[derive(clap::Args, Debug, Clone, Default, PartialEq)]
+pub struct ProjectOptions {
+    /// Name of the project [default: the directory name]
+    #[arg(long, value_name = "NAME")]
+    pub name: Option<String>,
+
+    /// Output file, relative to the current directory, or - for stdout [default: <NAME>.md in the output directory]
+    #[arg(short, long, value_name = "FILE")]
+    pub output: Option<String>,
+
+    /// Output format: markdown, html, json, jsonl or xml [default: from the output file extension]
+    #[arg(long, value_name = "FORMAT")]
+    pub format: Option<String>,
+
+    /// File to include, relative to the project directory, optionally with a line range
+    /// (e.g., src/main.rs#L10-80); may be repeated
+    #[arg(long = "file", value_name = "FILE")]
+    pub files: Vec<String>,
+
+    /// Directory to include recursively; may be repeated [default: .]
+    #[arg(long = "dir", value_name = "DIR")]
+    pub directories: Vec<String>,
+
+    /// Glob pattern files must match (e.g., 'src/**'); may be repeated
+    #[arg(long = "include", value_name = "GLOB")]
+    pub include_patterns: Vec<String>,
+
+    /// Path prefix or glob pattern of files to exclude (e.g., target, '*.log'); may be repeated
+    #[arg(long = "exclude", value_name = "PATTERN")]
+    pub exclude_patterns: Vec<String>,
+
+    /// Name or path of directories to skip anywhere in the tree; may be repeated
+    #[arg(long = "exclude-dir", value_name = "DIR")]
+    pub exclude_directories: Vec<String>,
+
+    /// Maximum file size in bytes
+    #[arg(long, value_name = "BYTES")]
+    pub max_file_size: Option<u64>,
+
+    /// Do not honor .gitignore, .ignore and .mdignore files
+    #[arg(long)]
+    pub no_ignore_files: bool,
+
+    /// Only include git-tracked files (tracked) or files with uncommitted changes (working_tree)
+    #[arg(long, value_name = "MODE", conflicts_with = "changed_since")]
+    pub git_filter: Option<String>,
+
+    /// Only include files changed since a ref or commit
+    #[arg(long, value_name = "REF")]
+    pub changed_since: Option<String>,
+
+    /// Describe the git revision in the header and the last commit of each file
+    #[arg(long)]
+    pub git_metadata: bool,
+
+    /// Document the changes since a ref or commit instead of the files
+    #[arg(long, value_name = "REF")]
+    pub diff_from: Option<String>,
+
+    /// End of the changes documented with --diff-from [default: HEAD]
+    #[arg(long, value_name = "REF", requires = "diff_from")]
+    pub diff_to: Option<String>,
+
+    /// Encoding of source files that are not valid UTF-8 (e.g., gbk)
+    #[arg(long, value_name = "ENCODING")]
+    pub source_encoding: Option<String>,
+
+    /// Encoding of the files matching a glob, as GLOB=ENCODING; may be repeated
+    #[arg(long = "encoding-override", value_name = "GLOB=ENCODING")]
+    pub encoding_overrides: Vec<String>,
+
+    /// Prefix each line with its line number
+    #[arg(long)]
+    pub line_numbers: bool,
+
+    /// Leave out the table of contents
+    #[arg(long)]
+    pub no_table_of_contents: bool,
+
+    /// Render the file tree as a list linking each file to its section
+    #[arg(long)]
+    pub linked_tree: bool,
+
+    /// Token encoding: cl100k_base, o200k_base, p50k_base, r50k_base or estimate
+    #[arg(long, value_name = "ENCODING")]
+    pub token_encoding: Option<String>,
+
+    /// Add a table of token counts per file
+    #[arg(long)]
+    pub token_summary: bool,
+
+    /// Add a table of line and byte counts per language
+    #[arg(long)]
+    pub statistics: bool,
+
+    /// Maximum number of tokens of file content
+    #[arg(long, value_name = "TOKENS")]
+    pub max_total_tokens: Option<usize>,
+
+    /// Maximum number of bytes of file content
+    #[arg(long, value_name = "BYTES")]
+    pub max_total_bytes: Option<u64>,
+
+    /// Glob pattern of files kept first within the content budget; may be repeated
+    #[arg(long = "priority", value_name = "GLOB")]
+    pub priority: Vec<String>,
+
+    /// Maximum number of tokens per output part
+    #[arg(long, value_name = "TOKENS")]
+    pub max_tokens_per_part: Option<usize>,
+
+    /// Maximum number of bytes per output part
+    #[arg(long, value_name = "BYTES")]
+    pub max_bytes_per_part: Option<u64>,
+
+    /// Markdown output language: en_us or zh_cn
+    #[arg(long, value_name = "LANG")]
+    pub markdown_lang: Option<String>,
+}
+
+impl ProjectOptions {
+    /// Returns `true` if no option is given.
+    pub fn is_empty(&self) -> bool {
+        *self == ProjectOptions::default()
+    }
+
+    /// Builds the configuration of a project directory from the options.
+    ///
+    /// The options are converted to the fields of a configuration file, so that they
+    /// are parsed and validated exactly like one.
+    ///
+    /// # Arguments
+    ///
+    /// * `project_dir` - Directory of the project.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Config>` - The validated configuration or an error.
+    pub fn to_config(&self, project_dir: &Path) -> Result<Config> {
+        let project_path = project_dir
+            .canonicalize()
+            .context(format!("Project directory does not exist: {}", project_dir.display()))?;
+        let project_name = match &self.name {
+            Some(name) => name.clone(),
+            None => project_path.file_name().unwrap_or_default().to_string_lossy().into_owned(),
+        };
+        let output_file = match &self.output {
+            // An absolute path is kept as is when joined to the output directory; "-"
+            // (stdout) keeps the default name, used by separators and parts
+            Some(output) if output != "-" => std::env::current_dir().context("Failed to read current directory")?.join(output),
+            _ => format!("{}.{}", file_stem_for(&project_name), extension_for(self.format.as_deref())).into(),
+        };
+        let directories = if self.directories.is_empty() && self.files.is_empty() {
+            vec![".".to_string()]
+        } else {
+            self.directories.clone()
+        };
+
+        let mut fields = Mapping::new();
+        insert(&mut fields, "project_name", &project_name)?;
+        insert(&mut fields, "project_path", &project_path)?;
+        insert(&mut fields, "output_file", &output_file)?;
+        insert(&mut fields, "files", &self.files)?;
+        insert(&mut fields, "directories", &directories)?;
+        insert(&mut fields, "include_patterns", &self.include_patterns)?;
+        insert(&mut fields, "exclude_patterns", &self.exclude_patterns)?;
+        insert(&mut fields, "exclude_directories", &self.exclude_directories)?;
+        insert(&mut fields, "respect_ignore_files", &!self.no_ignore_files)?;
+        insert(&mut fields, "git_metadata", &self.git_metadata)?;
+        insert(&mut fields, "line_numbers", &self.line_numbers)?;
+        insert(&mut fields, "table_of_contents", &!self.no_table_of_contents)?;
+        insert(&mut fields, "linked_tree", &self.linked_tree)?;
+        insert(&mut fields, "token_summary", &self.token_summary)?;
+        insert(&mut fields, "statistics", &self.statistics)?;
+        insert(&mut fields, "priority", &self.priority)?;
+        insert_some(&mut fields, "output_format", &self.format)?;
+        insert_some(&mut fields, "max_file_size", &self.max_file_size)?;
+        insert_some(&mut fields, "source_encoding", &self.source_encoding)?;
+        insert_some(&mut fields, "token_encoding", &self.token_encoding)?;
+        insert_some(&mut fields, "max_total_tokens", &self.max_total_tokens)?;
+        insert_some(&mut fields, "max_total_bytes", &self.max_total_bytes)?;
+        insert_some(&mut fields, "max_tokens_per_part", &self.max_tokens_per_part)?;
+        insert_some(&mut fields, "max_bytes_per_part", &self.max_bytes_per_part)?;
+        insert_some(&mut fields, "markdown_lang", &self.markdown_lang)?;
+
+        if let Some(mode) = &self.git_filter {
+            insert(&mut fields, "git_filter", mode)?;
+        }
+        if let Some(changed_since) = &self.changed_since {
+            let mut git_filter = Mapping::new();
+            insert(&mut git_filter, "changed_since", changed_since)?;
+            fields.insert("git_filter".into(), Value::Mapping(git_filter));
+        }
+        if let Some(from) = &self.diff_from {
+            let mut diff = Mapping::new();
+            insert(&mut diff, "from", from)?;
+            insert_some(&mut diff, "to", &self.diff_to)?;
+            fields.insert("diff".into(), Value::Mapping(diff));
+        }
+        let mut encoding_overrides = Vec::new();
+        for encoding_override in &self.encoding_overrides {
+            let (pattern, encoding) = encoding_override
+                .split_once('=')
+                .context(format!("Invalid encoding override (expected GLOB=ENCODING): {}", encoding_override))?;
+            let mut fields = Mapping::new();
+            insert(&mut fields, "pattern", pattern)?;
+            insert(&mut fields, "encoding", encoding)?;
+            encoding_overrides.push(Value::Mapping(fields));
+        }
+        fields.insert("encoding_overrides".into(), Value::Sequence(encoding_overrides));
+
+        Config::from_value(Value::Mapping(fields))
+            .context(format!("Invalid project options for: {}", project_path.display()))
+    }
+}
+
+/// Inserts a field into a mapping.
+fn insert<T: Serialize + ?Sized>(fields: &mut Mapping, key: &str, value: &T) -> Result<()> {
+    fields.insert(key.into(), serde_yaml::to_value(value)?);
+    Ok(())
+}
+
+/// Inserts a field into a mapping if it has a value.
+fn insert_some<T: Serialize>(fields: &mut Mapping, key: &str, value: &Option<T>) -> Result<()> {
+    match value {
+        Some(value) => insert(fields, key, value),
+        None => Ok(()),
+    }
+}
+
+/// Returns the output file extension of an output format, "md" by default.
+fn extension_for(format: Option<&str>) -> &'static str {
+    match format {
+        Some("html") => "html",
+        Some("json") => "json",
+        Some("jsonl") => "jsonl",
+        Some("xml") => "xml",
+        _ => "md",
+    }
+}
+
+/// Returns a file name for a project name, e.g. "my_project" for "My Project".
+pub fn file_stem_for(project_name: &str) -> String {
+    let stem: String = project_name
+        .trim()
+        .to_lowercase()
+        .chars()
+        .map(|c| if c.is_alphanumeric() || c == '-' { c } else { '_' })
+        .collect();
+    stem.trim_matches('_').to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::Parser;
+    use std::path::PathBuf;
+    use tempfile::TempDir;
+    use crate::config::{GitFilter, OutputFormat};
+
+    #[derive(Parser)]
+    struct TestArgs {
+        #[command(flatten)]
+        options: ProjectOptions,
+    }
+
+    fn parse(args: &[&str]) -> ProjectOptions {
+        TestArgs::parse_from(std::iter::once("test").chain(args.iter().copied())).options
+    }
+
+    #[test]
+    fn test_to_config() {
+        let temp_dir = TempDir::new().unwrap();
+        let project_dir = temp_dir.path().join("My Repo");
+        std::fs::create_dir(&project_dir).unwrap();
+
+        let config = parse(&[]).to_config(&project_dir).unwrap();
+        assert_eq!(config.project_name, "My Repo");
+        assert_eq!(config.output_file, PathBuf::from("my_repo.md"));
+        assert_eq!(config.directories[0].path(), Path::new("."));
+        assert!(config.respect_ignore_files);
+        assert!(config.table_of_contents);
+
+        let options = parse(&[
+            "--name", "Demo", "-o", "/tmp/out.json", "--include", "src/**", "--exclude", "target",
+            "--changed-since", "main", "--encoding-override", "legacy/**=gbk", "--no-ignore-files",
+            "--max-total-tokens", "1000",
+        ]);
+        let config = options.to_config(&project_dir).unwrap();
+        assert_eq!(config.project_name, "Demo");
+        assert_eq!(config.output_file, PathBuf::from("/tmp/out.json"));
+        assert_eq!(config.output_format(), OutputFormat::Json);
+        assert_eq!(config.include_patterns, ["src/**"]);
+        assert_eq!(config.exclude_patterns, ["target"]);
+        assert_eq!(config.git_filter, Some(GitFilter::ChangedSince { changed_since: "main".to_string() }));
+        assert_eq!(config.encoding_overrides[0].encoding, "gbk");
+        assert!(!config.respect_ignore_files);
+        assert_eq!(config.max_total_tokens, Some(1000));
+
+        // Options are validated like a configuration file
+        assert!(parse(&["--format", "pdf"]).to_config(&project_dir).is_err());
+        assert!(parse(&["--max-file-size", "0"]).to_config(&project_dir).is_err());
+        assert!(parse(&[]).to_config(&temp_dir.path().join("missing")).is_err());
+    }
+}
@@ -0,0 +1,180 @@
+use std::time::Duration;
+use anyhow::Result;
+use serde::Serialize;
+
+/// Format of the summary printed after generating the documentation.
+#[derive(clap::ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SummaryFormat {
+    /// Aligned text table.
+    Table,
+    /// JSON document.
+    Json,
+}
+
+/// Outcome of generating the documentation of a project.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "status", content = "error", rename_all = "lowercase")]
+pub enum ProjectStatus {
+    /// The documentation was written.
+    Ok,
+    /// The project failed with an error.
+    Failed(String),
+}
+
+/// Report of the documentation generated for a project.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ProjectReport {
+    /// Name of the project: its configuration name or directory.
+    pub project: String,
+    /// Number of files documented.
+    pub files: usize,
+    /// Number of bytes written.
+    pub bytes: u64,
+    /// Time spent on the project.
+    #[serde(rename = "duration_ms", serialize_with = "serialize_millis")]
+    pub duration: Duration,
+    /// Outcome of the project.
+    #[serde(flatten)]
+    pub status: ProjectStatus,
+}
+
+/// Summary of a run, serialized as JSON.
+#[derive(Serialize)]
+struct Summary<'a> {
+    succeeded: usize,
+    failed: usize,
+    projects: &'a [ProjectReport],
+}
+
+fn serialize_millis<S: serde::Serializer>(duration: &Duration, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_u128(duration.as_millis())
+}
+
+/// Returns the number of failed projects.
+pub fn failures(reports: &[ProjectReport]) -> usize {
+    reports.iter().filter(|report| report.status != ProjectStatus::Ok).count()
+}
+
+/// Renders the summary of a run.
+///
+/// # Arguments
+///
+/// * `reports` - Reports of the processed projects.
+/// * `format` - Format of the summary.
+///
+/// # Returns
+///
+/// * `Result<String>` - The summary, ending with a newline.
+pub fn render_summary(reports: &[ProjectReport], format: SummaryFormat) -> Result<String> {
+    match format {
+        SummaryFormat::Table => Ok(render_table(reports)),
+        SummaryFormat::Json => {
+            let failed = failures(reports);
+            let summary = Summary { succeeded: reports.len() - failed, failed, projects: reports };
+            Ok(serde_json::to_string_pretty(&summary)? + "\n")
+        }
+    }
+}
+
+/// Renders the reports as a table with aligned columns, followed by a total line.
+fn render_table(reports: &[ProjectReport]) -> String {
+    let header = ["Project", "Files", "Bytes", "Duration", "Status"];
+    let rows: Vec<[String; 5]> = reports
+        .iter()
+        .map(|report| [
+            report.project.clone(),
+            report.files.to_string(),
+            report.bytes.to_string(),
+            format!("{:.2}s", report.duration.as_secs_f64()),
+            match &report.status {
+                ProjectStatus::Ok => "ok".to_string(),
+                // Validation errors list one problem per line
+                ProjectStatus::Failed(error) => format!("failed: {}", error.lines().collect::<Vec<_>>().join("; ")),
+            },
+        ])
+        .collect();
+
+    let mut widths = header.map(str::len);
+    for row in &rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+
+    let mut table = String::new();
+    let mut push_row = |cells: [&str; 5]| {
+        // Text columns are left-aligned, numbers right-aligned; the last column is not padded
+        let line = format!(
+            "{:<w0$}  {:>w1$}  {:>w2$}  {:>w3$}  {}",
+            cells[0], cells[1], cells[2], cells[3], cells[4],
+            w0 = widths[0], w1 = widths[1], w2 = widths[2], w3 = widths[3],
+        );
+        table.push_str(line.trim_end());
+        table.push('\n');
+    };
+    push_row(header);
+    for row in &rows {
+        push_row([&row[0], &row[1], &row[2], &row[3], &row[4]]);
+    }
+
+    let failed = failures(reports);
+    table.push_str(&format!("{} succeeded, {} failed\n", reports.len() - failed, failed));
+    table
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn reports() -> Vec<ProjectReport> {
+        vec![
+            ProjectReport {
+                project: "alpha".to_string(),
+                files: 12,
+                bytes: 34567,
+                duration: Duration::from_millis(1250),
+                status: ProjectStatus::Ok,
+            },
+            ProjectReport {
+                project: "epub_reader".to_string(),
+                files: 0,
+                bytes: 0,
+                duration: Duration::from_millis(3),
+                status: ProjectStatus::Failed("Project path does not exist: D:\\epub".to_string()),
+            },
+            ProjectReport {
+                project: "broken".to_string(),
+                files: 0,
+                bytes: 0,
+                duration: Duration::from_millis(1),
+                status: ProjectStatus::Failed("Invalid configuration: Project name cannot be empty\nInvalid markdown_lang: fr".to_string()),
+            },
+        ]
+    }
+
+    #[test]
+    fn test_render_table() {
+        let table = render_summary(&reports(), SummaryFormat::Table).unwrap();
+        assert_eq!(table, "\
+Project      Files  Bytes  Duration  Status
+alpha           12  34567     1.25s  ok
+epub_reader      0      0     0.00s  failed: Project path does not exist: D:\\epub
+broken           0      0     0.00s  failed: Invalid configuration: Project name cannot be empty; Invalid markdown_lang: fr
+1 succeeded, 2 failed
+");
+    }
+
+    #[test]
+    fn test_render_json() {
+        let json = render_summary(&reports(), SummaryFormat::Json).unwrap();
+        let summary: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(summary["succeeded"], 1);
+        assert_eq!(summary["failed"], 2);
+        assert_eq!(summary["projects"][0]["project"], "alpha");
+        assert_eq!(summary["projects"][0]["duration_ms"], 1250);
+        assert_eq!(summary["projects"][0]["status"], "ok");
+        assert!(summary["projects"][0].get("error").is_none());
+        assert_eq!(summary["projects"][1]["status"], "failed");
+        assert_eq!(summary["projects"][1]["error"], "Project path does not exist: D:\\epub");
+    }
+}
@@ -0,0 +1,287 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+use crate::file_processor::FileContent;
+use crate::language::{self, CommentSyntax, Languages};
+
+/// Number of files listed as the largest files of a project.
+const LARGEST_FILES: usize = 10;
+
+/// Blank, comment and code line counts.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct LineCounts {
+    /// Lines containing only whitespace.
+    pub blank: usize,
+    /// Lines containing only comments.
+    pub comment: usize,
+    /// Lines containing code, possibly followed by a comment.
+    pub code: usize,
+}
+
+impl LineCounts {
+    /// Returns the total number of lines.
+    pub fn total(&self) -> usize {
+        self.blank + self.comment + self.code
+    }
+
+    fn add(&mut self, other: LineCounts) {
+        self.blank += other.blank;
+        self.comment += other.comment;
+        self.code += other.code;
+    }
+}
+
+/// Statistics of the files of one language.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LanguageStats {
+    /// Display name of the language.
+    pub language: String,
+    /// Number of files.
+    pub files: usize,
+    /// Line counts of all files.
+    pub lines: LineCounts,
+    /// Total size of the files in bytes.
+    pub bytes: u64,
+}
+
+/// Size of a single file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileSize {
+    /// Path of the file.
+    pub path: PathBuf,
+    /// Display name of the language of the file.
+    pub language: String,
+    /// Number of lines.
+    pub lines: usize,
+    /// Size in bytes.
+    pub bytes: u64,
+}
+
+/// Statistics of the text files of a project.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProjectStats {
+    /// Statistics per language, most code lines first.
+    pub languages: Vec<LanguageStats>,
+    /// Largest files, largest first.
+    pub largest_files: Vec<FileSize>,
+}
+
+impl ProjectStats {
+    /// Collects statistics of the text files of a project.
+    ///
+    /// Binary and omitted files are not counted. Languages are detected as for code
+    /// fences, and comment lines are recognized with the comment syntax of the language.
+    ///
+    /// # Arguments
+    ///
+    /// * `files` - List of files with their paths and contents.
+    /// * `languages` - Language definitions.
+    ///
+    /// # Returns
+    ///
+    /// * `ProjectStats` - The statistics of the files.
+    pub fn collect(files: &[(PathBuf, FileContent)], languages: &Languages) -> Self {
+        let mut by_language: HashMap<String, LanguageStats> = HashMap::new();
+        let mut file_sizes = Vec::new();
+
+        for (file_path, content) in files {
+            let FileContent::Text(text) = content else { continue };
+            let language = language::detect_language(file_path, Some(text), languages);
+            let lines = count_lines(text, languages.comment_syntax(&language));
+            let bytes = text.len() as u64;
+
+            let stats = by_language.entry(language.name.clone()).or_insert_with(|| LanguageStats {
+                language: language.name.clone(),
+                files: 0,
+                lines: LineCounts::default(),
+                bytes: 0,
+            });
+            stats.files += 1;
+            stats.lines.add(lines);
+            stats.bytes += bytes;
+
+            file_sizes.push(FileSize {
+                path: file_path.clone(),
+                language: language.name,
+                lines: lines.total(),
+                bytes,
+            });
+        }
+
+        let mut languages: Vec<LanguageStats> = by_language.into_values().collect();
+        languages.sort_by(|a, b| b.lines.code.cmp(&a.lines.code).then_with(|| a.language.cmp(&b.language)));
+        file_sizes.sort_by(|a, b| b.bytes.cmp(&a.bytes).then_with(|| a.path.cmp(&b.path)));
+        file_sizes.truncate(LARGEST_FILES);
+
+        ProjectStats { languages, largest_files: file_sizes }
+    }
+
+    /// Returns the statistics of all languages together.
+    pub fn total(&self) -> LanguageStats {
+        let mut total = LanguageStats {
+            language: String::new(),
+            files: 0,
+            lines: LineCounts::default(),
+            bytes: 0,
+        };
+        for stats in &self.languages {
+            total.files += stats.files;
+            total.lines.add(stats.lines);
+            total.bytes += stats.bytes;
+        }
+        total
+    }
+}
+
+/// Counts the blank, comment and code lines of a text.
+///
+/// A line is a comment line if it starts with a line comment marker or lies within a
+/// block comment; any other non-blank line is a code line. Comment markers inside
+/// string literals are not recognized.
+///
+/// # Arguments
+///
+/// * `text` - The text to count.
+/// * `syntax` - Comment syntax of the language, if known.
+///
+/// # Returns
+///
+/// * `LineCounts` - The line counts.
+pub fn count_lines(text: &str, syntax: Option<&CommentSyntax>) -> LineCounts {
+    let empty = CommentSyntax::default();
+    let syntax = syntax.unwrap_or(&empty);
+    let mut counts = LineCounts::default();
+    // End marker of the block comment the current line is in, if any
+    let mut block_end: Option<&str> = None;
+
+    for line in text.lines() {
+        let trimmed = line.trim();
+
+        if let Some(end) = block_end {
+            counts.comment += 1;
+            if let Some(position) = trimmed.find(end) {
+                block_end = open_block(&trimmed[position + end.len()..], syntax);
+            }
+            continue;
+        }
+
+        // Block openers are checked first, as some start with the line marker (e.g., `#=`)
+        if trimmed.is_empty() {
+            counts.blank += 1;
+        } else if let Some((start, end)) = syntax.block.iter().find(|(start, _)| trimmed.starts_with(start.as_str())) {
+            let rest = &trimmed[start.len()..];
+            match rest.find(end.as_str()) {
+                // Code may follow a block comment closed on the same line
+                Some(position) if !rest[position + end.len()..].trim().is_empty() => {
+                    counts.code += 1;
+                    block_end = open_block(&rest[position + end.len()..], syntax);
+                }
+                Some(_) => counts.comment += 1,
+                None => {
+                    counts.comment += 1;
+                    block_end = Some(end);
+                }
+            }
+        } else if syntax.line.iter().any(|marker| trimmed.starts_with(marker.as_str())) {
+            counts.comment += 1;
+        } else {
+            counts.code += 1;
+            block_end = open_block(trimmed, syntax);
+        }
+    }
+
+    counts
+}
+
+/// Returns the end marker of a block comment left open at the end of a line, if any.
+///
+/// Outside block comments, a line comment marker ends the search, since block openers
+/// after it are commented out.
+fn open_block<'a>(line: &str, syntax: &'a CommentSyntax) -> Option<&'a str> {
+    let mut open: Option<&str> = None;
+    let mut rest = line;
+    loop {
+        match open {
+            Some(end) => match rest.find(end) {
+                Some(position) => {
+                    rest = &rest[position + end.len()..];
+                    open = None;
+                }
+                None => return open,
+            },
+            None => {
+                let next = syntax
+                    .block
+                    .iter()
+                    .filter_map(|(start, end)| rest.find(start.as_str()).map(|position| (position, start, end)))
+                    .min_by_key(|(position, _, _)| *position);
+                let (position, start, end) = next?;
+                // At the same position, the block opener wins (e.g., `#=` over `#`)
+                let line_comment = syntax.line.iter().filter_map(|marker| rest.find(marker.as_str())).min();
+                if line_comment.is_some_and(|comment| comment < position) {
+                    return None;
+                }
+                rest = &rest[position + start.len()..];
+                open = Some(end.as_str());
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+
+    #[test]
+    fn test_count_lines() {
+        let syntax = CommentSyntax {
+            line: vec!["//".to_string()],
+            block: vec![("/*".to_string(), "*/".to_string())],
+        };
+        let text = "// header\n\nfn main() { /* start\n   still comment\n   end */\n    run(); // trailing\n/* one */\n/* a */ code();\n}\n";
+        let counts = count_lines(text, Some(&syntax));
+        assert_eq!(counts, LineCounts { blank: 1, comment: 4, code: 4 });
+        assert_eq!(counts.total(), 9);
+
+        assert_eq!(count_lines("a\n\n# b\n", None), LineCounts { blank: 1, comment: 0, code: 2 });
+
+        // Block openers after a line comment marker are commented out
+        let text = "let a = 1; // see /* here\nlet b = 2;\n/* x */ f(); // not /* open\ng();\n";
+        assert_eq!(count_lines(text, Some(&syntax)), LineCounts { blank: 0, comment: 0, code: 4 });
+
+        // Block openers that start with the line marker
+        let languages = language::default_languages();
+        let syntax_of = |file_name: &str| {
+            let language = language::detect_language(Path::new(file_name), None, &languages);
+            languages.comment_syntax(&language).cloned()
+        };
+        let julia = syntax_of("main.jl");
+        let text = "#= block\n   comment =#\n# line\nx = 1\n";
+        assert_eq!(count_lines(text, julia.as_ref()), LineCounts { blank: 0, comment: 3, code: 1 });
+        let nim = syntax_of("main.nim");
+        let text = "#[ block\n   comment ]#\necho 1\n";
+        assert_eq!(count_lines(text, nim.as_ref()), LineCounts { blank: 0, comment: 2, code: 1 });
+    }
+
+    #[test]
+    fn test_collect() {
+        let files = vec![
+            (PathBuf::from("/p/a.rs"), FileContent::Text("// a\nfn a() {}\n".to_string())),
+            (PathBuf::from("/p/b.rs"), FileContent::Text("fn b() {}\n\nfn c() {}\n".to_string())),
+            (PathBuf::from("/p/c.py"), FileContent::Text("# c\n".to_string())),
+            (PathBuf::from("/p/d.bin"), FileContent::Omitted { size: 100 }),
+        ];
+        let stats = ProjectStats::collect(&files, &language::default_languages());
+
+        assert_eq!(stats.languages.len(), 2);
+        assert_eq!(stats.languages[0], LanguageStats {
+            language: "Rust".to_string(),
+            files: 2,
+            lines: LineCounts { blank: 1, comment: 1, code: 3 },
+            bytes: 36,
+        });
+        assert_eq!(stats.languages[1].lines.comment, 1);
+        assert_eq!(stats.total().files, 3);
+        assert_eq!(stats.largest_files[0].path, PathBuf::from("/p/b.rs"));
+    }
+}
@@ -0,0 +1,111 @@
+use std::collections::HashSet;
+use std::path::PathBuf;
+use rayon::prelude::*;
+use tiktoken_rs::CoreBPE;
+use crate::config::TokenEncoding;
+use crate::file_processor::FileContent;
+
+/// Average number of characters per token used by the `estimate` encoding.
+const CHARS_PER_TOKEN: usize = 4;
+
+/// Counts tokens using one of the bundled BPE vocabularies, or a character-based estimate.
+pub struct TokenCounter {
+    encoding: TokenEncoding,
+    bpe: Option<&'static CoreBPE>,
+}
+
+/// Token counts of the files of a project.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TokenStats {
+    /// Encoding used to count the tokens.
+    pub encoding: TokenEncoding,
+    /// Token count of each file, in the order the files were given.
+    pub files: Vec<(PathBuf, usize)>,
+    /// Sum of all file token counts.
+    pub total: usize,
+}
+
+impl TokenStats {
+    /// Removes files whose content was omitted and recomputes the total.
+    pub fn exclude_omitted(&mut self, files: &[(PathBuf, FileContent)]) {
+        let omitted: HashSet<&PathBuf> = files
+            .iter()
+            .filter(|(_, content)| matches!(content, FileContent::Omitted { .. }))
+            .map(|(file_path, _)| file_path)
+            .collect();
+        self.files.retain(|(file_path, _)| !omitted.contains(file_path));
+        self.total = self.files.iter().map(|(_, tokens)| tokens).sum();
+    }
+}
+
+impl TokenCounter {
+    /// Creates a token counter for the given encoding.
+    ///
+    /// The vocabularies are embedded in the binary, so no network access is needed.
+    pub fn new(encoding: TokenEncoding) -> Self {
+        let bpe = match encoding {
+            TokenEncoding::Cl100kBase => Some(tiktoken_rs::cl100k_base_singleton()),
+            TokenEncoding::O200kBase => Some(tiktoken_rs::o200k_base_singleton()),
+            TokenEncoding::P50kBase => Some(tiktoken_rs::p50k_base_singleton()),
+            TokenEncoding::R50kBase => Some(tiktoken_rs::r50k_base_singleton()),
+            TokenEncoding::Estimate => None,
+        };
+        TokenCounter { encoding, bpe }
+    }
+
+    /// Returns the number of tokens in the given text.
+    pub fn count(&self, text: &str) -> usize {
+        match self.bpe {
+            Some(bpe) => bpe.encode_ordinary(text).len(),
+            None => text.chars().count().div_ceil(CHARS_PER_TOKEN),
+        }
+    }
+
+    /// Counts the tokens of each file in parallel. Binary and omitted files count as zero
+    /// tokens.
+    ///
+    /// # Arguments
+    ///
+    /// * `files` - List of files with their paths and contents.
+    ///
+    /// # Returns
+    ///
+    /// * `TokenStats` - Per-file and total token counts.
+    pub fn count_files(&self, files: &[(PathBuf, FileContent)]) -> TokenStats {
+        let counts: Vec<(PathBuf, usize)> = files
+            .par_iter()
+            .map(|(path, content)| {
+                let tokens = match content {
+                    FileContent::Text(text) => self.count(text),
+                    FileContent::Binary(_) | FileContent::Omitted { .. } => 0,
+                };
+                (path.clone(), tokens)
+            })
+            .collect();
+
+        TokenStats {
+            encoding: self.encoding,
+            total: counts.iter().map(|(_, tokens)| tokens).sum(),
+            files: counts,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_count_tokens() {
+        let files = vec![
+            (PathBuf::from("a.txt"), FileContent::Text("hello world".to_string())),
+            (PathBuf::from("b.txt"), FileContent::Text("hello".to_string())),
+        ];
+
+        let stats = TokenCounter::new(TokenEncoding::Cl100kBase).count_files(&files);
+        assert_eq!(stats.files, vec![(PathBuf::from("a.txt"), 2), (PathBuf::from("b.txt"), 1)]);
+        assert_eq!(stats.total, 3);
+
+        assert_eq!(TokenCounter::new(TokenEncoding::Estimate).count("hello world"), 3);
+    }
+}
@@ -1,12 +1,41 @@
 use std::path::{Path, PathBuf};
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, HashMap};
 use anyhow::Result;
+use crate::file_processor::FileContent;
+use crate::git_repository::{ChangeStatus, FileDiff};
+use crate::html_generator::escape_html;
+
+/// Marker appended to binary files in the tree.
+const BINARY_MARKER: &str = " [binary]";
+
+/// Marker appended to files omitted to fit the content budget.
+const OMITTED_MARKER: &str = " [omitted]";
+
+/// Markers appended to files of a diff document.
+const ADDED_MARKER: &str = " [added]";
+const MODIFIED_MARKER: &str = " [modified]";
+const DELETED_MARKER: &str = " [deleted]";
+
+/// Represents a file in the project tree.
+#[derive(Debug)]
+struct TreeFile {
+    name: String,
+    path: PathBuf,
+    marker: Option<&'static str>,
+}
+
+impl TreeFile {
+    /// Returns the name of the file followed by its marker, if any.
+    fn label(&self) -> String {
+        format!("{}{}", self.name, self.marker.unwrap_or(""))
+    }
+}
 
 /// Represents a directory in the project tree.
 #[derive(Debug)]
 struct Directory {
     name: String,
-    files: Vec<String>,
+    files: Vec<TreeFile>,
     subdirectories: BTreeMap<String, Directory>,
 }
 
@@ -19,25 +48,46 @@ impl Directory {
         }
     }
 
-    fn add_file(&mut self, file: String) {
+    fn add_file(&mut self, file: TreeFile) {
         self.files.push(file);
     }
 }
 
+/// Returns the marker of a file in a project snapshot, if any.
+fn content_marker(content: &FileContent) -> Option<&'static str> {
+    match content {
+        FileContent::Binary(_) => Some(BINARY_MARKER),
+        FileContent::Omitted { .. } => Some(OMITTED_MARKER),
+        FileContent::Text(_) => None,
+    }
+}
+
+/// Returns the marker of a changed file in a diff document.
+pub fn change_marker(status: ChangeStatus) -> &'static str {
+    match status {
+        ChangeStatus::Added => ADDED_MARKER,
+        ChangeStatus::Modified => MODIFIED_MARKER,
+        ChangeStatus::Deleted => DELETED_MARKER,
+    }
+}
+
 /// Builds a directory tree from a list of files.
 ///
 /// # Arguments
 ///
-/// * `files` - List of files with their paths and contents.
+/// * `files` - List of files with their paths and markers.
 /// * `project_root` - Path to the project root directory.
 ///
 /// # Returns
 ///
 /// * `Directory` - The root directory of the tree.
-fn build_directory_tree(files: &[(PathBuf, String)], project_root: &Path) -> Directory {
+fn build_directory_tree<'a>(
+    files: impl IntoIterator<Item = (&'a PathBuf, Option<&'static str>)>,
+    project_root: &Path,
+) -> Directory {
     let mut root = Directory::new("".to_string());
 
-    for (file_path, _) in files {
+    for (file_path, marker) in files {
         let relative_path = file_path.strip_prefix(project_root).unwrap_or(file_path);
         let components: Vec<String> = relative_path
             .components()
@@ -51,7 +101,11 @@ fn build_directory_tree(files: &[(PathBuf, String)], project_root: &Path) -> Dir
                 let sub_dir = entry.or_insert_with(|| Directory::new(component.clone()));
                 current_dir = sub_dir;
             } else {
-                current_dir.add_file(component.clone());
+                current_dir.add_file(TreeFile {
+                    name: component.clone(),
+                    path: file_path.clone(),
+                    marker,
+                });
             }
         }
     }
@@ -102,7 +156,7 @@ fn directory_tree_to_string(
         current_item += 1;
         let is_last_item = current_item == total_items;
         let connector = if is_last_item { "└── " } else { "├── " };
-        tree.push_str(&format!("{}{}{}\n", new_indent, connector, file));
+        tree.push_str(&format!("{}{}{}\n", new_indent, connector, file.label()));
     }
 
     // Process subdirectories
@@ -129,12 +183,202 @@ fn directory_tree_to_string(
 /// * `Result<String>` - The tree structure as a string.
 pub fn generate_tree(
     project_name: &str,
-    files: &[(PathBuf, String)],
+    files: &[(PathBuf, FileContent)],
+    project_root: &Path,
+) -> Result<String> {
+    let markers = files.iter().map(|(path, content)| (path, content_marker(content)));
+    let mut root = build_directory_tree(markers, project_root);
+    root.name = project_name.to_string();
+
+    let tree = directory_tree_to_string(&root, "", true, true);
+    Ok(tree)
+}
+
+/// Generates a tree-like structure of file paths, without markers.
+///
+/// # Arguments
+///
+/// * `project_name` - Name of the project.
+/// * `files` - List of file paths.
+/// * `project_root` - Path to the project root directory.
+///
+/// # Returns
+///
+/// * `Result<String>` - The tree structure as a string.
+pub fn generate_path_tree(
+    project_name: &str,
+    files: &[PathBuf],
+    project_root: &Path,
+) -> Result<String> {
+    let mut root = build_directory_tree(files.iter().map(|path| (path, None)), project_root);
+    root.name = project_name.to_string();
+
+    let tree = directory_tree_to_string(&root, "", true, true);
+    Ok(tree)
+}
+
+/// Generates a tree-like structure of the files changed between two revisions.
+///
+/// Each file is marked as `[added]`, `[modified]` or `[deleted]`.
+///
+/// # Arguments
+///
+/// * `project_name` - Name of the project.
+/// * `files` - List of changed files with their paths.
+/// * `project_root` - Path to the project root directory.
+///
+/// # Returns
+///
+/// * `Result<String>` - The tree structure as a string.
+pub fn generate_diff_tree(
+    project_name: &str,
+    files: &[(PathBuf, FileDiff)],
     project_root: &Path,
 ) -> Result<String> {
-    let mut root = build_directory_tree(files, project_root);
+    let markers = files.iter().map(|(path, diff)| (path, Some(change_marker(diff.status))));
+    let mut root = build_directory_tree(markers, project_root);
     root.name = project_name.to_string();
 
     let tree = directory_tree_to_string(&root, "", true, true);
     Ok(tree)
 }
+
+/// Converts a directory tree to a nested Markdown list linking files to their sections.
+///
+/// # Arguments
+///
+/// * `directory` - The directory to convert.
+/// * `depth` - Nesting depth of the directory.
+/// * `links` - Mapping of file paths to the links of their sections.
+///
+/// # Returns
+///
+/// * `String` - The Markdown list representation of the directory tree.
+fn directory_tree_to_list(
+    directory: &Directory,
+    depth: usize,
+    links: &HashMap<PathBuf, String>,
+) -> String {
+    let indent = "  ".repeat(depth + 1);
+    let mut list = String::new();
+
+    // Process files
+    for file in &directory.files {
+        let name = escape_link_text(&file.name);
+        let marker = file.marker.unwrap_or("");
+        match links.get(&file.path) {
+            Some(link) => list.push_str(&format!("{}- [{}]({}){}\n", indent, name, link, marker)),
+            None => list.push_str(&format!("{}- {}{}\n", indent, name, marker)),
+        }
+    }
+
+    // Process subdirectories
+    for sub_dir in directory.subdirectories.values() {
+        list.push_str(&format!("{}- **{}/**\n", indent, escape_link_text(&sub_dir.name)));
+        list.push_str(&directory_tree_to_list(sub_dir, depth + 1, links));
+    }
+
+    list
+}
+
+/// Escapes characters that would end or alter Markdown link text.
+fn escape_link_text(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace('[', "\\[")
+        .replace(']', "\\]")
+        .replace('*', "\\*")
+        .replace('_', "\\_")
+}
+
+/// Generates the project tree as a nested Markdown list, linking each file to its section.
+///
+/// # Arguments
+///
+/// * `project_name` - Name of the project.
+/// * `files` - List of files with their paths.
+/// * `project_root` - Path to the project root directory.
+/// * `links` - Mapping of file paths to the links of their sections (e.g., "#file-mainrs").
+///
+/// # Returns
+///
+/// * `Result<String>` - The tree structure as a Markdown list.
+pub fn generate_linked_tree(
+    project_name: &str,
+    files: &[(PathBuf, FileContent)],
+    project_root: &Path,
+    links: &HashMap<PathBuf, String>,
+) -> Result<String> {
+    let markers = files.iter().map(|(path, content)| (path, content_marker(content)));
+    let root = build_directory_tree(markers, project_root);
+
+    let mut tree = format!("- **{}**\n", escape_link_text(project_name));
+    tree.push_str(&directory_tree_to_list(&root, 0, links));
+    Ok(tree)
+}
+
+/// Converts a directory tree to nested HTML lists with collapsible directories.
+///
+/// # Arguments
+///
+/// * `directory` - The directory to convert.
+/// * `anchors` - Mapping of file paths to the ids of their sections.
+///
+/// # Returns
+///
+/// * `String` - The HTML representation of the directory's content.
+fn directory_tree_to_html(directory: &Directory, anchors: &HashMap<PathBuf, String>) -> String {
+    let mut html = String::from("<ul>\n");
+
+    // Process files
+    for file in &directory.files {
+        let name = escape_html(&file.name);
+        let marker = match file.marker {
+            Some(marker) => format!("<span class=\"marker\">{}</span>", escape_html(marker)),
+            None => String::new(),
+        };
+        match anchors.get(&file.path) {
+            Some(anchor) => html.push_str(&format!("<li><a href=\"#{}\">{}</a>{}</li>\n", escape_html(anchor), name, marker)),
+            None => html.push_str(&format!("<li>{}{}</li>\n", name, marker)),
+        }
+    }
+
+    // Process subdirectories
+    for sub_dir in directory.subdirectories.values() {
+        html.push_str(&format!(
+            "<li><details open><summary>{}/</summary>\n{}</details></li>\n",
+            escape_html(&sub_dir.name),
+            directory_tree_to_html(sub_dir, anchors)
+        ));
+    }
+
+    html.push_str("</ul>\n");
+    html
+}
+
+/// Generates the project tree as nested HTML lists, linking each file to its section.
+///
+/// # Arguments
+///
+/// * `project_name` - Name of the project.
+/// * `files` - List of files with their paths.
+/// * `project_root` - Path to the project root directory.
+/// * `anchors` - Mapping of file paths to the ids of their sections.
+///
+/// # Returns
+///
+/// * `Result<String>` - The tree structure as HTML.
+pub fn generate_html_tree(
+    project_name: &str,
+    files: &[(PathBuf, FileContent)],
+    project_root: &Path,
+    anchors: &HashMap<PathBuf, String>,
+) -> Result<String> {
+    let markers = files.iter().map(|(path, content)| (path, content_marker(content)));
+    let root = build_directory_tree(markers, project_root);
+
+    Ok(format!(
+        "<ul>\n<li><details open><summary>{}</summary>\n{}</details></li>\n</ul>\n",
+        escape_html(project_name),
+        directory_tree_to_html(&root, anchors)
+    ))
+}
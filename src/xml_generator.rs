@@ -0,0 +1,111 @@
+use std::path::PathBuf;
+use anyhow::Result;
+use crate::config::Config;
+use crate::file_processor::FileContent;
+use crate::language::Languages;
+use crate::html_generator::escape_html;
+use crate::{language, tree_generator};
+
+/// Generates an XML-tagged document of a project, intended for LLM prompts.
+///
+/// Each file is wrapped in a `<file path="..." language="...">` element and the project
+/// tree in a `<directory_structure>` element. Text and attribute values are escaped, and
+/// file contents are wrapped in CDATA sections so that models see the source exactly as
+/// written while file boundaries stay unambiguous.
+///
+/// # Arguments
+///
+/// * `config` - The project configuration.
+/// * `files` - List of files with their paths and contents.
+/// * `languages` - Language definitions.
+///
+/// # Returns
+///
+/// * `Result<String>` - The generated XML content or an error.
+pub fn generate_xml(
+    config: &Config,
+    files: Vec<(PathBuf, FileContent)>,
+    languages: &Languages,
+) -> Result<String> {
+    let project_name = config.project_name.as_str();
+    let project_root = config.project_path.as_path();
+
+    // Sort files for consistent output
+    let mut sorted_files = files;
+    sorted_files.sort_by(|a, b| a.0.cmp(&b.0));
+
+    let mut xml = format!("<project name=\"{}\">\n", escape_html(project_name));
+
+    // Add the project file tree
+    xml.push_str("<directory_structure>\n");
+    xml.push_str(&format!("{}\n", escape_html(project_name)));
+    xml.push_str(&escape_html(&tree_generator::generate_tree(project_name, &sorted_files, project_root)?));
+    xml.push_str("</directory_structure>\n");
+
+    // Add file contents
+    xml.push_str("<files>\n");
+    for (file_path, content) in &sorted_files {
+        let relative_path = file_path.strip_prefix(project_root).unwrap_or(file_path);
+        let path = escape_html(&relative_path.to_string_lossy().replace('\\', "/"));
+        let language = escape_html(&language::detect_language(file_path, content.as_text(), languages).name);
+
+        match content {
+            FileContent::Text(text) => {
+                xml.push_str(&format!("<file path=\"{}\" language=\"{}\">\n", path, language));
+                xml.push_str(&cdata(text));
+                xml.push_str("\n</file>\n");
+            }
+            FileContent::Binary(info) => {
+                xml.push_str(&format!(
+                    "<file path=\"{}\" language=\"{}\" binary=\"true\" size=\"{}\" mime_type=\"{}\" sha256=\"{}\"/>\n",
+                    path, language, info.size, info.mime_type, info.sha256
+                ));
+            }
+            FileContent::Omitted { size } => {
+                xml.push_str(&format!(
+                    "<file path=\"{}\" language=\"{}\" omitted=\"true\" size=\"{}\"/>\n",
+                    path, language, size
+                ));
+            }
+        }
+    }
+    xml.push_str("</files>\n</project>\n");
+
+    Ok(xml)
+}
+
+/// Wraps text in a CDATA section, splitting any `]]>` across two sections.
+fn cdata(text: &str) -> String {
+    format!("<![CDATA[{}]]>", text.replace("]]>", "]]]]><![CDATA[>"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::test_config;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_generate_xml() {
+        let config = test_config("project_name: Demo & Co");
+        let files = vec![
+            (
+                PathBuf::from("/project/README.md"),
+                FileContent::Text("```rust\nlet a = 1 < 2;\n```".to_string()),
+            ),
+            (
+                PathBuf::from("/project/src/xml.rs"),
+                FileContent::Text("let end = \"</file></files>\";\nlet cdata = \"]]>\";\n".to_string()),
+            ),
+        ];
+        let languages = Languages::from(HashMap::from([("md".to_string(), "Markdown".to_string())]));
+
+        let xml = generate_xml(&config, files, &languages).unwrap();
+        assert!(xml.starts_with("<project name=\"Demo &amp; Co\">\n<directory_structure>\nDemo &amp; Co\n"));
+        assert!(xml.contains("<file path=\"README.md\" language=\"Markdown\">\n<![CDATA[```rust\nlet a = 1 < 2;\n```]]>\n</file>\n"));
+        assert!(xml.contains(
+            "<file path=\"src/xml.rs\" language=\"Text\">\n\
+             <![CDATA[let end = \"</file></files>\";\nlet cdata = \"]]]]><![CDATA[>\";\n]]>\n</file>\n</files>\n</project>\n"
+        ));
+    }
+}